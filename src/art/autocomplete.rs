@@ -0,0 +1,111 @@
+//! The precomputed prefix→top-k completion side table embedded in a
+//! compiled ART file (see `ArtCompiler::new_with_autocomplete`).
+//!
+//! The table is keyed by node offset rather than by the prefix text itself:
+//! a lookup only ever happens once `ArtSearch` has already descended to the
+//! node a given prefix resolves to, which is exactly where `search_prefix`
+//! would otherwise have to start a full subtree walk from. Each entry holds
+//! the `top_n` most frequent words in that node's subtree (the node's own
+//! compressed path included), in the same `word` shape `collect_subtree`
+//! would produce for it.
+
+use crate::WordFrequency;
+
+use core::convert::TryInto;
+use core::num::NonZeroU32;
+use std::collections::HashMap;
+
+/// How densely the prefix→top-k side table is computed, passed to
+/// `ArtCompiler::new_with_autocomplete`.
+#[derive(Debug, Clone, Copy)]
+pub struct AutocompleteConfig {
+    /// A node's subtree must contain at least this many distinct words
+    /// before its offset gets an entry in the side table.
+    pub min_words: usize,
+    /// How many of a node's most frequent completions are kept.
+    pub top_n: usize,
+}
+
+/// Keep only the `top_n` highest-frequency entries, ties broken by the
+/// suffix itself so the result is deterministic (mirroring `WordData`'s own
+/// frequency-desc, word-asc ordering).
+pub(super) fn keep_top_n(entries: &mut Vec<(Vec<u8>, WordFrequency)>, top_n: usize) {
+    entries.sort_by(|(a_word, a_freq), (b_word, b_freq)| {
+        b_freq.cmp(a_freq).then(a_word.cmp(b_word))
+    });
+    entries.truncate(top_n);
+}
+
+/// Serialize the table as a `u64` entry count followed by each entry, in
+/// ascending node-offset order so a reader can stop a linear scan as soon
+/// as it passes the offset it's looking for: a `u64` node offset, a `u8`
+/// completion count, then that many `(u8 suffix length, suffix bytes, u32
+/// frequency)` records.
+pub(super) fn encode(table: &HashMap<usize, Vec<(Vec<u8>, WordFrequency)>>) -> Vec<u8> {
+    let mut offsets: Vec<usize> = table.keys().copied().collect();
+    offsets.sort();
+
+    let mut out = Vec::new();
+    out.extend_from_slice(&(offsets.len() as u64).to_le_bytes());
+
+    for offset in offsets {
+        let completions = &table[&offset];
+
+        out.extend_from_slice(&(offset as u64).to_le_bytes());
+        out.push(completions.len() as u8);
+
+        for (suffix, frequency) in completions {
+            out.push(suffix.len() as u8);
+            out.extend_from_slice(suffix);
+            out.extend_from_slice(&frequency.get().to_le_bytes());
+        }
+    }
+
+    out
+}
+
+/// Look up `offset`'s precomputed completions in the encoded table bytes.
+/// Returns `None` if the offset has no entry: either its subtree never
+/// crossed `min_words`, or the feature was disabled and `bytes` is empty.
+pub(super) fn lookup(bytes: &[u8], offset: usize) -> Option<Vec<(Vec<u8>, WordFrequency)>> {
+    if bytes.len() < 8 {
+        return None;
+    }
+
+    let entry_count = u64::from_le_bytes(bytes[0..8].try_into().unwrap()) as usize;
+    let mut cursor = 8;
+
+    // Entries are sorted ascending by offset, so a linear scan can bail out
+    // as soon as it has passed where `offset` would be; the table is small
+    // relative to the tree it describes, so this isn't worth a real binary
+    // search over the variable-length records.
+    for _ in 0..entry_count {
+        let entry_offset = u64::from_le_bytes(bytes[cursor..cursor + 8].try_into().unwrap()) as usize;
+        cursor += 8;
+
+        let nb_completions = bytes[cursor] as usize;
+        cursor += 1;
+
+        if entry_offset > offset {
+            return None;
+        }
+
+        let mut completions = Vec::with_capacity(nb_completions);
+        for _ in 0..nb_completions {
+            let suffix_len = bytes[cursor] as usize;
+            cursor += 1;
+            let suffix = bytes[cursor..cursor + suffix_len].to_vec();
+            cursor += suffix_len;
+            let frequency = u32::from_le_bytes(bytes[cursor..cursor + 4].try_into().unwrap());
+            cursor += 4;
+
+            completions.push((suffix, NonZeroU32::new(frequency).unwrap()));
+        }
+
+        if entry_offset == offset {
+            return Some(completions);
+        }
+    }
+
+    None
+}
@@ -0,0 +1,370 @@
+//! A portable, explicitly little-endian, self-describing encoding for ART
+//! nodes, independent of the native mmap-shaped layout `art::mod` casts raw
+//! bytes into with `get`/`node_at`. That layout is only loadable on a
+//! machine with the same pointer width and endianness that wrote it; this
+//! module is what `ArtSearch::save_portable`/`load_portable` go through
+//! instead, so a compiled index can be shipped to and loaded on another one.
+//!
+//! The portable format has its own header (magic bytes, a version distinct
+//! from `FORMAT_VERSION`, and an endianness marker) and its own node
+//! encoding (a `NodeKind` discriminant byte followed by fixed-width
+//! little-endian fields), so it can evolve independently of the native
+//! layout.
+
+use super::{Node0, Node4, Node16, Node48, Node256, NodeHeader, NodeKind, Header, FORMAT_VERSION};
+// Renamed so the native header construction below reads unambiguously next
+// to this module's own (differently named) `MAGIC`/`HEADER_SIZE`.
+use super::MAGIC as NATIVE_MAGIC;
+use super::HEADER_SIZE as NATIVE_HEADER_SIZE;
+use super::merkle;
+use crate::WordFrequency;
+
+use core::convert::TryInto;
+use core::mem::size_of;
+use core::num::{NonZeroU32, NonZeroUsize};
+
+/// The magic bytes identifying a portable ART file. Distinct from the
+/// native format's `MAGIC` so the two are never mistaken for one another.
+pub const MAGIC: [u8; 8] = *b"DDTRIEP\0";
+
+/// This module's own format version, bumped independently of the native
+/// `FORMAT_VERSION` whenever the portable byte layout changes.
+pub const VERSION: u8 = 1;
+
+/// Every multi-byte field below is written little-endian; this marker lets
+/// a decoder reject a file claiming otherwise instead of silently
+/// misreading it on a big-endian host.
+const LITTLE_ENDIAN: u8 = 1;
+
+/// How many bytes the portable header occupies before the first node.
+pub const HEADER_SIZE: usize = MAGIC.len() + 1 + 1 + 8 + 8;
+
+/// One node's worth of data, decoded from its portable bytes. Flat and
+/// variant-agnostic, unlike the native `Node0`..`Node256` structs, since the
+/// portable format has no need for a dedicated struct per child-count tier.
+#[derive(Debug, Clone)]
+pub struct DecodedNode {
+    /// The native node kind this was originally compiled as, preserved so
+    /// `NativeWriter` can rebuild the exact same tier instead of
+    /// recomputing it from the child count.
+    pub kind: NodeKind,
+    pub frequency: Option<WordFrequency>,
+    pub path: Vec<u8>,
+    /// `(edge byte, child offset)` pairs, in ascending key order. Offsets
+    /// are relative to the end of the portable header, same as every other
+    /// offset in this format.
+    pub children: Vec<(u8, usize)>,
+}
+
+/// Appends nodes to a growing portable byte buffer. A node must only be
+/// encoded once every child it points to has already been encoded (mirroring
+/// the bottom-up order `ArtCompiler` already writes nodes in), so that a
+/// child's offset is known by the time its parent needs it.
+pub struct NodeEncoder {
+    buffer: Vec<u8>,
+}
+
+impl NodeEncoder {
+    pub fn new() -> Self {
+        NodeEncoder { buffer: Vec::new() }
+    }
+
+    /// The offset (relative to the end of the portable header) the next
+    /// encoded node will land at.
+    pub fn next_offset(&self) -> usize {
+        self.buffer.len()
+    }
+
+    /// Encode one node, returning the offset it was written at.
+    pub fn encode(
+        &mut self,
+        kind: NodeKind,
+        frequency: Option<WordFrequency>,
+        path: &[u8],
+        children: &[(u8, usize)],
+    ) -> usize {
+        let offset = self.next_offset();
+
+        self.buffer.push(kind as u8);
+        self.buffer.extend_from_slice(&frequency.map_or(0, NonZeroU32::get).to_le_bytes());
+        self.buffer.push(path.len() as u8);
+        self.buffer.extend_from_slice(path);
+        self.buffer.push(children.len() as u8);
+        for &(key, child_offset) in children {
+            self.buffer.push(key);
+            self.buffer.extend_from_slice(&(child_offset as u64).to_le_bytes());
+        }
+
+        offset
+    }
+
+    /// Finish encoding, producing the full portable file bytes: the
+    /// self-describing header followed by every encoded node.
+    pub fn finish(self, node_count: usize, root: usize) -> Vec<u8> {
+        let mut out = Vec::with_capacity(HEADER_SIZE + self.buffer.len());
+
+        out.extend_from_slice(&MAGIC);
+        out.push(VERSION);
+        out.push(LITTLE_ENDIAN);
+        out.extend_from_slice(&(node_count as u64).to_le_bytes());
+        out.extend_from_slice(&(root as u64).to_le_bytes());
+        out.extend_from_slice(&self.buffer);
+
+        out
+    }
+}
+
+/// Reads nodes back out of a portable byte buffer produced by `NodeEncoder`.
+pub struct NodeDecoder<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> NodeDecoder<'a> {
+    /// Validate and strip the portable header, returning the root's offset
+    /// together with a decoder for the node bytes that follow.
+    pub fn open(bytes: &'a [u8]) -> Result<(usize, Self), String> {
+        if bytes.len() < HEADER_SIZE {
+            return Err(String::from("File is too small to contain a portable ART header"));
+        }
+
+        if &bytes[0..MAGIC.len()] != &MAGIC[..] {
+            return Err(String::from("Not a portable ART file: wrong magic bytes"));
+        }
+
+        let version = bytes[MAGIC.len()];
+        if version != VERSION {
+            return Err(format!(
+                "Unsupported portable ART version {} (expected {})",
+                version, VERSION
+            ));
+        }
+
+        let endianness = bytes[MAGIC.len() + 1];
+        if endianness != LITTLE_ENDIAN {
+            return Err(String::from("Portable ART file was written with a different byte order"));
+        }
+
+        let root_offset = MAGIC.len() + 2 + 8;
+        let root = u64::from_le_bytes(bytes[root_offset..root_offset + 8].try_into().unwrap()) as usize;
+
+        Ok((root, NodeDecoder { bytes: &bytes[HEADER_SIZE..] }))
+    }
+
+    /// Decode the node at `offset` (relative to the end of the portable
+    /// header).
+    pub fn decode(&self, offset: usize) -> Result<DecodedNode, String> {
+        let too_short = || format!("Truncated portable ART node at offset {}", offset);
+
+        let kind_byte = *self.bytes.get(offset).ok_or_else(too_short)?;
+        let kind = match kind_byte {
+            0 => NodeKind::Node0,
+            1 => NodeKind::Node4,
+            2 => NodeKind::Node16,
+            3 => NodeKind::Node48,
+            4 => NodeKind::Node256,
+            other => return Err(format!("Unknown node kind {} at offset {}", other, offset)),
+        };
+        let mut cursor = offset + 1;
+
+        let frequency_bytes: [u8; 4] = self.bytes.get(cursor..cursor + 4)
+            .ok_or_else(too_short)?
+            .try_into().unwrap();
+        let frequency = NonZeroU32::new(u32::from_le_bytes(frequency_bytes));
+        cursor += 4;
+
+        let path_length = *self.bytes.get(cursor).ok_or_else(too_short)? as usize;
+        cursor += 1;
+        let path = self.bytes.get(cursor..cursor + path_length).ok_or_else(too_short)?.to_vec();
+        cursor += path_length;
+
+        let child_count = *self.bytes.get(cursor).ok_or_else(too_short)? as usize;
+        cursor += 1;
+
+        let mut children = Vec::with_capacity(child_count);
+        for _ in 0..child_count {
+            let key = *self.bytes.get(cursor).ok_or_else(too_short)?;
+            cursor += 1;
+            let child_offset_bytes: [u8; 8] = self.bytes.get(cursor..cursor + 8)
+                .ok_or_else(too_short)?
+                .try_into().unwrap();
+            children.push((key, u64::from_le_bytes(child_offset_bytes) as usize));
+            cursor += 8;
+        }
+
+        Ok(DecodedNode { kind, frequency, path, children })
+    }
+}
+
+/// Decode a whole portable file back into this crate's native, mmap-shaped
+/// on-disk layout (the same bytes `ArtCompiler` writes), so it can be mapped
+/// through the ordinary `ArtSearch::load` path.
+pub fn rebuild_native(bytes: &[u8]) -> Result<Vec<u8>, String> {
+    let (root, decoder) = NodeDecoder::open(bytes)?;
+
+    let mut writer = NativeWriter::new();
+    writer.write(&decoder, root, true)?;
+
+    Ok(writer.finish())
+}
+
+/// Rebuilds the native node region, reserving the root's fixed slot up
+/// front exactly like `ArtCompiler` does, then appending every other node.
+struct NativeWriter {
+    nodes: Vec<u8>,
+    node_count: usize,
+}
+
+impl NativeWriter {
+    fn new() -> Self {
+        let mut nodes = Vec::new();
+        nodes.resize(size_of::<Node256>(), 0);
+
+        NativeWriter { nodes, node_count: 0 }
+    }
+
+    fn write(&mut self, decoder: &NodeDecoder, offset: usize, is_root: bool) -> Result<usize, String> {
+        let decoded = decoder.decode(offset)?;
+
+        let mut children = Vec::with_capacity(decoded.children.len());
+        for &(key, child_offset) in &decoded.children {
+            let native_offset = self.write(decoder, child_offset, false)?;
+            children.push((key, native_offset));
+        }
+
+        let mut header = NodeHeader {
+            frequency: decoded.frequency,
+            kind: NodeKind::Node256,
+            nb_children: children.len() as u8,
+            path_length: decoded.path.len() as u8,
+            path: [0; 7],
+        };
+        header.path[..decoded.path.len()].copy_from_slice(&decoded.path);
+
+        let mut pointers = [None; 256];
+        for &(key, native_offset) in &children {
+            pointers[key as usize] = NonZeroUsize::new(native_offset);
+        }
+
+        let template = Node256 { header, pointers };
+
+        // The root always takes its fixed, reserved slot and is never
+        // compacted down to a smaller tier, same as `ArtCompiler`.
+        let buffer = if is_root {
+            serialize::<Node256>(template)
+        } else {
+            match decoded.kind {
+                NodeKind::Node0 => serialize::<Node0>(template.into()),
+                NodeKind::Node4 => serialize::<Node4>(template.into()),
+                NodeKind::Node16 => serialize::<Node16>(template.into()),
+                NodeKind::Node48 => serialize::<Node48>(template.into()),
+                NodeKind::Node256 => serialize::<Node256>(template.into()),
+            }
+        };
+
+        let native_offset = if is_root {
+            self.nodes[0..buffer.len()].copy_from_slice(&buffer);
+            0
+        } else {
+            let offset = self.nodes.len();
+            self.nodes.extend_from_slice(&buffer);
+            offset
+        };
+
+        self.node_count += 1;
+
+        Ok(native_offset)
+    }
+
+    fn finish(self) -> Vec<u8> {
+        let header = Header {
+            magic: NATIVE_MAGIC,
+            version: FORMAT_VERSION,
+            root: 0,
+            node_count: self.node_count,
+            // The portable format doesn't carry a Bloom filter, autocomplete
+            // table or Merkle tree, so a file rebuilt through it has none;
+            // `ArtSearch` treats each zeroed length as "not embedded" and
+            // falls back accordingly (walking the tree, or nothing to
+            // verify against).
+            bloom_len: 0,
+            autocomplete_len: 0,
+            merkle_len: 0,
+            merkle_root: [0; merkle::HASH_SIZE],
+        };
+
+        let header_bytes = unsafe {
+            let ptr = &header as *const Header as *const u8;
+            core::slice::from_raw_parts(ptr, NATIVE_HEADER_SIZE)
+        };
+
+        let mut out = Vec::with_capacity(NATIVE_HEADER_SIZE + self.nodes.len());
+        out.extend_from_slice(header_bytes);
+        out.extend_from_slice(&self.nodes);
+
+        out
+    }
+}
+
+fn serialize<T: Sized>(node: T) -> Vec<u8> {
+    let buffer = unsafe {
+        let ptr = &node as *const T as *const u8;
+        core::slice::from_raw_parts(ptr, size_of::<T>())
+    };
+
+    buffer.to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip_a_single_node() {
+        let mut encoder = NodeEncoder::new();
+        let frequency = WordFrequency::new(42);
+        let offset = encoder.encode(NodeKind::Node0, frequency, &[b'h', b'i'], &[]);
+        let bytes = encoder.finish(1, offset);
+
+        let (root, decoder) = NodeDecoder::open(&bytes).unwrap();
+        let node = decoder.decode(root).unwrap();
+
+        assert_eq!(node.kind, NodeKind::Node0);
+        assert_eq!(node.frequency, frequency);
+        assert_eq!(node.path, vec![b'h', b'i']);
+        assert!(node.children.is_empty());
+    }
+
+    #[test]
+    fn round_trip_children() {
+        let mut encoder = NodeEncoder::new();
+        let leaf = encoder.encode(NodeKind::Node0, WordFrequency::new(1), &[], &[]);
+        let root = encoder.encode(NodeKind::Node4, None, &[], &[(b'a', leaf)]);
+        let bytes = encoder.finish(2, root);
+
+        let (root, decoder) = NodeDecoder::open(&bytes).unwrap();
+        let node = decoder.decode(root).unwrap();
+
+        assert_eq!(node.children, vec![(b'a', leaf)]);
+
+        let child = decoder.decode(leaf).unwrap();
+        assert_eq!(child.frequency, WordFrequency::new(1));
+    }
+
+    #[test]
+    fn rejects_wrong_magic() {
+        let mut bytes = vec![0u8; HEADER_SIZE];
+        bytes[0] = b'X';
+
+        assert!(NodeDecoder::open(&bytes).is_err());
+    }
+
+    #[test]
+    fn rejects_unsupported_version() {
+        let mut encoder = NodeEncoder::new();
+        let root = encoder.encode(NodeKind::Node0, None, &[], &[]);
+        let mut bytes = encoder.finish(1, root);
+        bytes[MAGIC.len()] = VERSION + 1;
+
+        assert!(NodeDecoder::open(&bytes).is_err());
+    }
+}
@@ -1,12 +1,29 @@
 use crate::{Compiler, WordFrequency};
-use super::{NodeKind, NodeHeader, NodeOffset, Node0, Node4, Node16, Node48, Node256};
+use crate::bloom::BloomFilter;
+use crate::memory::{DiskMemory, MemoryAccess};
+use super::{NodeKind, NodeHeader, NodeOffset, NodeRef, Node0, Node4, Node16, Node48, Node256};
+use super::{Header, HEADER_SIZE, MAGIC, FORMAT_VERSION, node_at, node_size, read_header};
+use super::autocomplete::{self, AutocompleteConfig};
+use super::merkle;
 
+use std::collections::HashMap;
 use std::fs::{File, OpenOptions};
 use std::io::{Write, Seek, SeekFrom};
 
+use core::cmp::{min, max};
 use core::num::NonZeroUsize;
 use core::mem::size_of;
 
+/// The false positive rate targeted for the Bloom filter embedded in every
+/// compiled file. Low enough to reject the vast majority of exact misses,
+/// without the filter itself becoming a large fraction of the file.
+const BLOOM_FALSE_POSITIVE_RATE: f64 = 0.01;
+
+/// The fraction of the node region that is allowed to be dead (superseded
+/// by `ArtCompiler::update`/`remove`) before `build` performs a full
+/// compacting rewrite instead of leaving it in place.
+const DEFAULT_COMPACTION_THRESHOLD: f32 = 0.33;
+
 /// The compiler compile the ART structure into a stored
 /// equivalent that can be directly used.
 /// This compiler need to have each element added added in order
@@ -29,6 +46,12 @@ use core::mem::size_of;
 /// For pointer optimisation reason, the first node in the file is always
 /// the root node (even if it will be inserted last), as 0 index means that
 /// the node doesn't have a child.
+///
+/// `new`/`new_with_dedup` always require every word up front, in one sorted
+/// pass. `open_existing` instead reopens an already-compiled file and lets
+/// `update`/`remove` mutate it in place by appending new node versions and
+/// moving the root, leaving superseded ones as dead space until `build`
+/// decides it's worth a full compacting rewrite (see `compaction_threshold`).
 pub struct ArtCompiler {
     /// Where the nodes need to be written.
     file: File,
@@ -37,7 +60,107 @@ pub struct ArtCompiler {
     nodes: Vec<RAMNode>,
     /// The current index in the file so that each parent node know
     /// the index of its newly inserted child.
+    /// This index is relative to the end of the header.
     file_index: usize,
+    /// How many nodes have been written to the file, stored in the header.
+    node_count: usize,
+    /// Whether finalized subtrees are hash-consed against already-written
+    /// ones instead of always being appended as a fresh node.
+    dedup: bool,
+    /// The minimal-DAWG register used when `dedup` is set: a finalized
+    /// node's serialized bytes are hashed, and the hash maps to every
+    /// already-written node sharing it along with its bytes, so an exact
+    /// match can be told apart from a collision.
+    register: HashMap<u64, Vec<(usize, Vec<u8>)>>,
+    /// How many parents point at a given node offset. Kept so that a future
+    /// compaction/free pass knows a shared node can't be reclaimed until
+    /// every parent pointing at it has been dropped.
+    ref_counts: HashMap<usize, usize>,
+    /// Every word added so far. Not used to build the trie itself (that is
+    /// streamed straight to the file), only kept so `build` can size and
+    /// populate the embedded Bloom filter once the final word count is known.
+    words: Vec<Vec<u8>>,
+    /// The file this compiler is writing to, kept so `open_existing` and a
+    /// compacting rewrite can reopen it.
+    filename: String,
+    /// The offset of the current root node, relative to the end of the
+    /// header. Always `0` while building fresh (the root's fixed slot right
+    /// after the header); once opened through `open_existing`, `update`/
+    /// `remove` append a new root on every edit and move this instead.
+    root: usize,
+    /// How many of `file_index` bytes are still reachable from `root`. Grows
+    /// by a node's size every time one is written, and shrinks by a node's
+    /// size whenever `update`/`remove` supersede it with a new version.
+    /// Always equal to `file_index` while building fresh, since nothing is
+    /// ever superseded in that mode.
+    live_bytes: usize,
+    /// Whether this compiler is editing an already-compiled file (opened
+    /// through `open_existing`) rather than building a fresh one. Toggles
+    /// whether the root is pinned to its fixed slot right after the header
+    /// (fresh build) or appended and tracked through `root` like any other
+    /// node (incremental edit).
+    appending: bool,
+    /// Above what `unreachable_bytes / file_index` ratio `build` performs a
+    /// full compacting rewrite instead of leaving the appended dead space
+    /// in place. Only consulted when `appending` is set.
+    compaction_threshold: f32,
+    /// The size in bytes of the Bloom filter currently on disk, as last read
+    /// by `open_existing`/`refresh_from_disk` (zero for a fresh build). An
+    /// incremental `update`/`remove` doesn't rebuild the filter, only
+    /// re-persists the header with the new `root`, so it needs this to
+    /// leave `bloom_len` untouched.
+    bloom_len: usize,
+    /// When set, `write_to_file` accumulates a prefix→top-k completion table
+    /// (see `art::autocomplete`) alongside every node it writes, and `build`
+    /// embeds it in the compiled file. `None` makes the feature fully
+    /// zero-overhead: no table is built or written.
+    autocomplete: Option<AutocompleteConfig>,
+    /// The prefix→top-k side table being accumulated, keyed by the file
+    /// offset of the node it was computed for. Only populated when
+    /// `autocomplete` is set, and only ever grown by a dense build (through
+    /// `new_with_autocomplete`): an incremental `update`/
+    /// `remove` on a reopened file doesn't maintain it.
+    autocomplete_table: HashMap<usize, Vec<(Vec<u8>, WordFrequency)>>,
+    /// The size in bytes of the serialized autocomplete side table currently
+    /// on disk, as last read by `open_existing`/`refresh_from_disk`. Tracked
+    /// for the same reason as `bloom_len`: an incremental `update`/`remove`
+    /// re-persists the header without rebuilding either trailing region.
+    autocomplete_table_len: usize,
+    /// When set, `write_fresh` hashes every node's bytes into `leaf_hashes`
+    /// as it writes them, and `build` folds the result into a Merkle tree
+    /// (see `art::merkle`) embedded in the compiled file. `false` makes the
+    /// feature fully zero-overhead: nothing is hashed or written.
+    merkle: bool,
+    /// One leaf hash per node actually written so far (a node reused
+    /// through `dedup`'s hash-consing isn't written again, so it isn't
+    /// rehashed either), in file order. Only populated when `merkle` is set.
+    leaf_hashes: Vec<[u8; merkle::HASH_SIZE]>,
+    /// The size in bytes of the serialized Merkle tree currently on disk, as
+    /// last read by `open_existing`/`refresh_from_disk`. Tracked for the
+    /// same reason as `bloom_len`: an incremental `update`/`remove`
+    /// re-persists the header without rebuilding the tree.
+    merkle_len: usize,
+    /// The Merkle root currently on disk, mirrored the same way as
+    /// `merkle_len` so it can be passed back unchanged to `write_header`.
+    merkle_root: [u8; merkle::HASH_SIZE],
+}
+
+/// What `ArtCompiler::decode_path` found while following a word down the
+/// on-disk tree of an already-compiled file.
+enum Descent {
+    /// The word resolves to an exact existing node, now staged as the last
+    /// entry of `self.nodes`, ready to have its frequency set or cleared.
+    Found,
+    /// The word runs past every matched node and diverges cleanly at a node
+    /// boundary (no compressed path needs splitting): `self.nodes` holds the
+    /// path up to the fork point and `remaining` is what's left to add,
+    /// exactly what `add_rec` expects to take over from.
+    Diverges { remaining: Vec<u8> },
+    /// The word diverges in the middle of an existing node's compressed
+    /// path, which would require splitting that path to insert. This
+    /// incremental path doesn't support that; the caller falls back to a
+    /// full compacting rebuild instead.
+    NeedsSplit,
 }
 
 /// Node kept in RAM.
@@ -56,11 +179,52 @@ struct RAMNode {
     /// as having two children means that the first one needs
     /// to be wrote on the file.
     /// If this node is the root one, this value will be ignored.
-    child: u8
+    child: u8,
+    /// How many distinct words live under this node, counting itself.
+    /// Accumulated from already-flushed children as they are written, and
+    /// finalized (by adding one for this node's own frequency, if any)
+    /// right before this node itself is written. Only meaningful when
+    /// `ArtCompiler::autocomplete` is set.
+    word_count: usize,
+    /// The node's top-k completions accumulated from already-flushed
+    /// children, each already including that child's own compressed path
+    /// and its connecting byte from this node. Finalized into this node's
+    /// own `autocomplete_table` entry (with this node's own frequency and
+    /// compressed path folded in) right before it is written. Only
+    /// meaningful when `ArtCompiler::autocomplete` is set.
+    completions: Vec<(Vec<u8>, WordFrequency)>,
 }
 
 impl ArtCompiler {
     pub fn new(filename: &str) -> Result<Self, String> {
+        Self::new_with_dedup(filename, false)
+    }
+
+    /// Same as `new`, but with the DAWG-style subtree deduplication pass
+    /// toggled on: a finalized subtree that is byte-identical to one
+    /// already written is shared instead of duplicated, at the cost of
+    /// keeping a register of every written node's bytes in memory.
+    pub fn new_with_dedup(filename: &str, dedup: bool) -> Result<Self, String> {
+        Self::new_with_options(filename, dedup, None, false)
+    }
+
+    /// Same as `new`, but with a prefix→top-k completion side table (see
+    /// `art::autocomplete`) computed during `build` and embedded in the
+    /// compiled file, so `ArtSearch::search_prefix` can answer common
+    /// prefixes in O(prefix length) instead of walking their whole subtree.
+    pub fn new_with_autocomplete(filename: &str, dedup: bool, autocomplete: AutocompleteConfig) -> Result<Self, String> {
+        Self::new_with_options(filename, dedup, Some(autocomplete), false)
+    }
+
+    /// Same as `new`, but with a Merkle tree (see `art::merkle`) computed
+    /// over every written node during `build` and embedded in the compiled
+    /// file, so `ArtSearch::verify` can detect corruption and `root_hash`
+    /// can confirm two files hold byte-identical dictionaries.
+    pub fn new_with_merkle(filename: &str, dedup: bool) -> Result<Self, String> {
+        Self::new_with_options(filename, dedup, None, true)
+    }
+
+    fn new_with_options(filename: &str, dedup: bool, autocomplete: Option<AutocompleteConfig>, merkle: bool) -> Result<Self, String> {
 
         let mut file = OpenOptions::new()
                         .read(true)
@@ -70,6 +234,11 @@ impl ArtCompiler {
                         .open(filename)
                         .map_err(|error| format!("Can't open file \"{}\" (reason: {})", filename, error))?;
 
+        // Reserve the header, filled with the real values in build.
+        file
+            .write_all(&[0u8; HEADER_SIZE])
+            .map_err(|error| format!("Can't write to file: {}", error))?;
+
         // Reserve the root node.
         let buffer = unsafe {
             let ptr = &core::mem::zeroed::<Node256>() as *const Node256 as *const u8;
@@ -94,24 +263,273 @@ impl ArtCompiler {
                         },
                         pointers: [None; 256]
                     },
-                    child: 0
+                    child: 0,
+                    word_count: 0,
+                    completions: Vec::new(),
                 }
             ],
-            file_index: size_of::<Node256>()
+            file_index: size_of::<Node256>(),
+            node_count: 0,
+            dedup,
+            register: HashMap::new(),
+            ref_counts: HashMap::new(),
+            words: Vec::new(),
+            filename: filename.to_string(),
+            root: 0,
+            live_bytes: size_of::<Node256>(),
+            appending: false,
+            compaction_threshold: DEFAULT_COMPACTION_THRESHOLD,
+            bloom_len: 0,
+            autocomplete,
+            autocomplete_table: HashMap::new(),
+            autocomplete_table_len: 0,
+            merkle,
+            leaf_hashes: Vec::new(),
+            merkle_len: 0,
+            merkle_root: [0; merkle::HASH_SIZE],
         })
     }
+
+    /// Reopen an already-compiled file (written by `new`/`new_with_dedup`
+    /// and `build`) for incremental edits via `update`/`remove`, using
+    /// `DEFAULT_COMPACTION_THRESHOLD`.
+    pub fn open_existing(filename: &str) -> Result<Self, String> {
+        Self::open_existing_with_threshold(filename, DEFAULT_COMPACTION_THRESHOLD)
+    }
+
+    /// Same as `open_existing`, but with an explicit compaction threshold
+    /// instead of `DEFAULT_COMPACTION_THRESHOLD`.
+    pub fn open_existing_with_threshold(filename: &str, compaction_threshold: f32) -> Result<Self, String> {
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(filename)
+            .map_err(|error| format!("Can't open file \"{}\" (reason: {})", filename, error))?;
+
+        let mut compiler = ArtCompiler {
+            file,
+            nodes: Vec::new(),
+            file_index: 0,
+            node_count: 0,
+            dedup: false,
+            register: HashMap::new(),
+            ref_counts: HashMap::new(),
+            words: Vec::new(),
+            filename: filename.to_string(),
+            root: 0,
+            live_bytes: 0,
+            appending: true,
+            compaction_threshold,
+            bloom_len: 0,
+            // `open_existing` doesn't maintain the autocomplete table: an
+            // incremental `update`/`remove` only appends the narrow path it
+            // touches, not enough to keep a subtree-wide top-k in sync.
+            autocomplete: None,
+            autocomplete_table: HashMap::new(),
+            autocomplete_table_len: 0,
+            // Same reasoning applies to the Merkle tree: an incremental
+            // edit only rehashes the narrow path it touches, not every
+            // node, so it can't keep a whole-file tree in sync either.
+            merkle: false,
+            leaf_hashes: Vec::new(),
+            merkle_len: 0,
+            merkle_root: [0; merkle::HASH_SIZE],
+        };
+
+        compiler.refresh_from_disk()?;
+        Ok(compiler)
+    }
+
+    /// Re-read this file's header and node region from disk, resetting
+    /// every piece of bookkeeping `open_existing` derives. Used both to
+    /// open a compiled file for the first time and to resynchronize after
+    /// a compacting rebuild has replaced the file out from under an
+    /// in-progress editing session.
+    fn refresh_from_disk(&mut self) -> Result<(), String> {
+        let memory = DiskMemory::open(&self.filename, MemoryAccess::ReadOnly)?;
+        let header = read_header(&memory)?;
+
+        self.root = header.root;
+        self.node_count = header.node_count;
+        self.bloom_len = header.bloom_len;
+        self.autocomplete_table_len = header.autocomplete_len;
+        self.merkle_len = header.merkle_len;
+        self.merkle_root = header.merkle_root;
+        self.file_index = memory.len() - HEADER_SIZE - header.bloom_len - header.autocomplete_len - header.merkle_len;
+        self.live_bytes = measure_live_bytes(&memory, self.root);
+        self.words = collect_entries(&memory, self.root)
+            .into_iter()
+            .map(|(word, _)| word)
+            .collect();
+
+        drop(memory);
+
+        self.file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(&self.filename)
+            .map_err(|error| format!("Can't open file \"{}\" (reason: {})", self.filename, error))?;
+
+        // New node versions are appended right where the old Merkle tree,
+        // autocomplete table and Bloom filter used to start; `build`
+        // rebuilds the Bloom filter from scratch regardless of whether this
+        // session ends up compacting, and an edited file carries no Merkle
+        // tree or autocomplete table at all, so their old bytes are simply
+        // dropped here.
+        self.file
+            .set_len((HEADER_SIZE + self.file_index) as u64)
+            .map_err(|error| format!("Can't drop the old trailing side tables of \"{}\" (reason: {})", self.filename, error))?;
+
+        self.nodes.clear();
+        self.appending = true;
+
+        Ok(())
+    }
+
+    /// Write the validated header at the start of the file once every node
+    /// has been written, so that a reader can reject a foreign or truncated
+    /// file instead of mapping garbage.
+    fn write_header(&mut self, merkle_len: usize, merkle_root: [u8; merkle::HASH_SIZE], autocomplete_len: usize, bloom_len: usize) -> Result<(), String> {
+        self.file
+            .seek(SeekFrom::Start(0))
+            .map_err(|error| format!("Can't go to the start of the file: {}", error))?;
+
+        let header = Header {
+            magic: MAGIC,
+            version: FORMAT_VERSION,
+            root: self.root,
+            node_count: self.node_count,
+            bloom_len,
+            autocomplete_len,
+            merkle_len,
+            merkle_root,
+        };
+
+        let buffer = unsafe {
+            let ptr = &header as *const Header as *const u8;
+            std::slice::from_raw_parts(ptr, HEADER_SIZE)
+        };
+
+        self.file
+            .write_all(buffer)
+            .map_err(|error| format!("Can't write to file: {}", error))
+    }
+
+    /// Build the Bloom filter covering every word added so far, sized from
+    /// the final word count so it hits `BLOOM_FALSE_POSITIVE_RATE`, and
+    /// return its serialized bytes ready to be appended to the file.
+    fn build_bloom(&self) -> Vec<u8> {
+        let mut bloom: BloomFilter<Vec<u8>> = BloomFilter::with(
+            max(1, self.words.len()) as u64,
+            BLOOM_FALSE_POSITIVE_RATE,
+        );
+
+        for word in &self.words {
+            bloom.add(word);
+        }
+
+        bloom.bytes().to_vec()
+    }
 }
 
 impl Compiler for ArtCompiler {
     fn add(&mut self, word: &[u8], frequency: WordFrequency) {
+        self.words.push(word.to_vec());
         self.add_rec(word, frequency, 0).unwrap();
     }
 
     fn build(mut self) {
         self.move_to_file(0).unwrap();
+
+        if self.appending {
+            let unreachable_bytes = self.file_index.saturating_sub(self.live_bytes);
+            let ratio = unreachable_bytes as f32 / self.file_index.max(1) as f32;
+
+            if ratio > self.compaction_threshold {
+                // Dead space has piled up past the threshold: rather than
+                // patching around it, re-run the dense single-pass build on
+                // every word still reachable from the current root, exactly
+                // as if they had all been known up front to `new`.
+                self.compact();
+                return;
+            }
+        }
+
+        let (merkle_bytes, merkle_root) = if self.merkle {
+            let tree = merkle::build(self.leaf_hashes.clone());
+            (merkle::encode(&tree), merkle::root_of(&tree))
+        } else {
+            (Vec::new(), [0; merkle::HASH_SIZE])
+        };
+        let autocomplete_bytes = if self.autocomplete.is_some() {
+            autocomplete::encode(&self.autocomplete_table)
+        } else {
+            Vec::new()
+        };
+        let bloom_bytes = self.build_bloom();
+
+        // The node region's true end isn't where the cursor is left after
+        // `move_to_file` (the root is written last, but to its fixed slot
+        // right after the header), so seek there explicitly before
+        // appending the Merkle tree, the autocomplete table and the Bloom
+        // filter, in that order (the Bloom filter must stay last: its
+        // length alone is enough for `ArtSearch::load` to find it, counting
+        // back from the end of the file).
+        self.file
+            .seek(SeekFrom::Start((HEADER_SIZE + self.file_index) as u64))
+            .and_then(|_| self.file.write_all(&merkle_bytes))
+            .and_then(|_| self.file.write_all(&autocomplete_bytes))
+            .and_then(|_| self.file.write_all(&bloom_bytes))
+            .unwrap();
+
+        self.write_header(merkle_bytes.len(), merkle_root, autocomplete_bytes.len(), bloom_bytes.len()).unwrap();
     }
 }
 
+impl ArtCompiler {
+    /// Replace this file entirely with a dense rebuild of every word still
+    /// reachable from the current root, exactly what `new` would have
+    /// produced had every one of them been known up front. Used by `build`
+    /// once too much appended dead space has piled up.
+    fn compact(self) {
+        let memory = DiskMemory::open(&self.filename, MemoryAccess::ReadOnly)
+            .expect("file just written by this compiler must be readable");
+        let entries = collect_entries(&memory, self.root);
+        drop(memory);
+
+        rebuild_dense(&self.filename, entries);
+    }
+
+    /// Like `compact`, but also folds in one pending `update` whose
+    /// insertion point would otherwise require splitting an existing
+    /// compressed path (see `Descent::NeedsSplit`), and resynchronizes
+    /// `self` against the rebuilt file so this session can keep editing.
+    fn compacting_update(&mut self, word: &[u8], frequency: WordFrequency) -> Result<(), String> {
+        let memory = DiskMemory::open(&self.filename, MemoryAccess::ReadOnly)?;
+        let mut entries = collect_entries(&memory, self.root);
+        drop(memory);
+
+        entries.retain(|(existing, _)| existing.as_slice() != word);
+        entries.push((word.to_vec(), frequency));
+
+        rebuild_dense(&self.filename, entries);
+
+        self.refresh_from_disk()
+    }
+}
+
+/// Rebuild `filename` from scratch as a dense, single-pass compiled ART
+/// covering exactly `entries`, sorted by word as `Compiler::add` requires.
+fn rebuild_dense(filename: &str, mut entries: Vec<(Vec<u8>, WordFrequency)>) {
+    entries.sort();
+
+    let mut fresh = ArtCompiler::new(filename).unwrap();
+    for (word, frequency) in entries {
+        fresh.add(&word, frequency);
+    }
+    fresh.build();
+}
+
 impl ArtCompiler {
     /// Add the given word recursively.
     /// If a new trie path is created, the new one is written to file first.
@@ -145,7 +563,9 @@ impl ArtCompiler {
                         pointers: [None; 256]
                     },
 
-                    child: 0
+                    child: 0,
+                    word_count: 0,
+                    completions: Vec::new(),
                 });
 
                 self.nodes[node_index].node.pointers[word[0] as usize] = NodeOffset::new(parent_index + 1);
@@ -202,15 +622,15 @@ impl ArtCompiler {
 
             let is_root = self.nodes.len() <= 1;
 
-            // Modifying the parent to add the newly node that will be inserted.
-            if !is_root {
-                let parent_index = self.nodes.len() - 2;
-                let parent = &mut self.nodes[parent_index];
-                parent.node.pointers[parent.child as usize] = NonZeroUsize::new(self.file_index);
-            } else {
-                // The node have no parent, it's the root node, inserting it at the start of the file.
+            if is_root && !self.appending {
+                // The node have no parent, it's the root node, inserting it at
+                // the start of the node region, just after the header. An
+                // incrementally-edited file instead appends its new root
+                // like any other node and moves `self.root` to match, since
+                // the slot right after the header may already hold a still-
+                // referenced (or just superseded) older version.
                 self.file
-                    .seek(SeekFrom::Start(0))
+                    .seek(SeekFrom::Start(HEADER_SIZE as u64))
                     .map_err(|error| format!("Can't go to the end of the file: {}", error))?;
             }
 
@@ -229,39 +649,446 @@ impl ArtCompiler {
                 }
             };
 
-            // Advancing the file_index
-            self.file_index += match new_type {
-                NodeKind::Node0 => size_of::<Node0>(),
-                NodeKind::Node4 => size_of::<Node4>(),
-                NodeKind::Node16 => size_of::<Node16>(),
-                NodeKind::Node48 => size_of::<Node48>(),
-                NodeKind::Node256 => size_of::<Node256>()
-            };
-
-            fn write_node_to_file<T: Sized>(file: &mut File, node: T) -> Result<(), String> {
+            fn serialize<T: Sized>(node: T) -> Vec<u8> {
                 let buffer = unsafe {
                     let ptr = &node as *const T as *const u8;
                     std::slice::from_raw_parts(ptr, size_of::<T>())
                 };
 
-                file
-                    .write_all(buffer)
-                    .map_err(|error| format!("Can't write to file: {}", error))?;
+                buffer.to_vec()
+            }
+
+            // Transforming the node into its on-disk bytes. By the time a
+            // node is popped here every child pointer it holds has already
+            // been resolved to a real file offset, since children are
+            // always written before their parent.
+            let popped = self.nodes.pop().unwrap();
+            let node = popped.node;
+
+            // Saved before `node` is consumed by the `.into()` conversions
+            // below: this node's own compressed path, needed to fold its
+            // already-flushed children's completions (and its own
+            // frequency, if any) into its prefix→top-k entry.
+            let header_frequency = node.header.frequency;
+            let header_path = node.header.path;
+            let header_path_length = node.header.path_length as usize;
+
+            let buffer = match new_type {
+                NodeKind::Node0 => serialize::<Node0>(node.into()),
+                NodeKind::Node4 => serialize::<Node4>(node.into()),
+                NodeKind::Node16 => serialize::<Node16>(node.into()),
+                NodeKind::Node48 => serialize::<Node48>(node.into()),
+                NodeKind::Node256 => serialize::<Node256>(node.into()),
+            };
+
+            // The root is never shared (in a fresh build it takes its fixed
+            // slot); every other node is a candidate for sharing with an already-written, byte-identical
+            // subtree when deduplication is turned on.
+            let offset = if !is_root && self.dedup {
+                self.intern(buffer)?
+            } else {
+                self.write_fresh(&buffer)?
+            };
+
+            // Finalize this node's own prefix→top-k entry: its own
+            // frequency (if it completes a word) plus whatever its
+            // already-flushed children contributed, each extended by this
+            // node's own compressed path since a completion is relative to
+            // wherever a caller's descent landed, not to this node's child.
+            let mut own_word_count = 0;
+            let mut own_completions = Vec::new();
+
+            if let Some(config) = self.autocomplete {
+                own_word_count = popped.word_count + if header_frequency.is_some() { 1 } else { 0 };
+
+                if let Some(frequency) = header_frequency {
+                    own_completions.push((header_path[0..header_path_length].to_vec(), frequency));
+                }
+                for (suffix, frequency) in popped.completions {
+                    let mut full = header_path[0..header_path_length].to_vec();
+                    full.extend(suffix);
+                    own_completions.push((full, frequency));
+                }
+
+                autocomplete::keep_top_n(&mut own_completions, config.top_n);
+
+                if own_word_count >= config.min_words {
+                    self.autocomplete_table.insert(offset, own_completions.clone());
+                }
+            }
+
+            if is_root {
+                if self.appending {
+                    self.root = offset;
+                }
+            } else {
+                let parent_index = self.nodes.len() - 1;
+                let parent = &mut self.nodes[parent_index];
+                parent.node.pointers[parent.child as usize] = NonZeroUsize::new(offset);
+
+                if self.autocomplete.is_some() {
+                    let link_byte = parent.child;
+                    parent.word_count += own_word_count;
+                    parent.completions.extend(own_completions.into_iter().map(|(suffix, frequency)| {
+                        let mut full = vec![link_byte];
+                        full.extend(suffix);
+                        (full, frequency)
+                    }));
+                }
+
+                *self.ref_counts.entry(offset).or_insert(0) += 1;
+            }
+        }
+
+        Ok(())
+    }
 
-                Ok(())
+    /// Write `buffer` as a brand new node at the current end of the file,
+    /// returning the offset (relative to the end of the header) it landed at.
+    fn write_fresh(&mut self, buffer: &[u8]) -> Result<usize, String> {
+        // A fresh build's root is written last, but into its reserved slot
+        // at offset 0, already counted in `file_index` and `live_bytes`.
+        let pinned_root = !self.appending && self.nodes.is_empty();
+        let offset = if pinned_root { 0 } else { self.file_index };
+
+        self.file
+            .write_all(buffer)
+            .map_err(|error| format!("Can't write to file: {}", error))?;
+
+        if !pinned_root {
+            self.file_index += buffer.len();
+            self.live_bytes += buffer.len();
+        }
+        self.node_count += 1;
+
+        if self.merkle {
+            let hash = merkle::hash_leaf(buffer);
+            if pinned_root {
+                self.leaf_hashes.insert(0, hash);
+            } else {
+                self.leaf_hashes.push(hash);
             }
+        }
+
+        Ok(offset)
+    }
+
+    /// Hash-cons `buffer`: reuse the offset of an already-written node with
+    /// the exact same bytes instead of writing a duplicate, falling back to
+    /// `write_fresh` on a miss (or a hash collision with different bytes).
+    /// This is the classic Daciuk minimal-DAWG register, applicable here
+    /// because the trie is built bottom-up and a written node is immutable,
+    /// so a subtree is only ever considered for reuse once fully resolved.
+    fn intern(&mut self, buffer: Vec<u8>) -> Result<usize, String> {
+        let hash = Self::hash_node(&buffer);
 
-            // Transforming the node and inserting it in the file.
-            let node = self.nodes.pop().unwrap().node;
-            match new_type {
-                NodeKind::Node0 => write_node_to_file::<Node0>(&mut self.file, node.into())?,
-                NodeKind::Node4 => write_node_to_file::<Node4>(&mut self.file, node.into())?,
-                NodeKind::Node16 => write_node_to_file::<Node16>(&mut self.file, node.into())?,
-                NodeKind::Node48 => write_node_to_file::<Node48>(&mut self.file, node.into())?,
-                NodeKind::Node256 => write_node_to_file::<Node256>(&mut self.file, node.into())?,
+        if let Some(candidates) = self.register.get(&hash) {
+            if let Some(&(offset, _)) = candidates.iter().find(|(_, bytes)| *bytes == buffer) {
+                return Ok(offset);
+            }
+        }
+
+        let offset = self.write_fresh(&buffer)?;
+        self.register.entry(hash).or_insert_with(Vec::new).push((offset, buffer));
+
+        Ok(offset)
+    }
+
+    fn hash_node(buffer: &[u8]) -> u64 {
+        use std::collections::hash_map::DefaultHasher;
+        use std::hash::{Hash, Hasher};
+
+        let mut hasher = DefaultHasher::new();
+        buffer.hash(&mut hasher);
+        hasher.finish()
+    }
+}
+
+impl ArtCompiler {
+    /// Update `word`'s frequency in an already-compiled file (opened
+    /// through `open_existing`), inserting it if it wasn't present. Only
+    /// the path from the root down to `word` is rewritten, appended past
+    /// the current end of the file; every untouched sibling subtree keeps
+    /// its existing offset.
+    ///
+    /// If `word`'s insertion point would require splitting an existing
+    /// node's compressed path, this falls back to a full compacting
+    /// rebuild instead (see `Descent::NeedsSplit`), since the append-only
+    /// edit path doesn't implement path splitting.
+    pub fn update(&mut self, word: &[u8], frequency: WordFrequency) -> Result<(), String> {
+        let memory = DiskMemory::open(&self.filename, MemoryAccess::ReadOnly)?;
+        let (descent, superseded) = self.decode_path(&memory, word)?;
+        drop(memory);
+
+        match descent {
+            Descent::Found => {
+                let last = self.nodes.len() - 1;
+                if self.nodes[last].node.header.frequency == Some(frequency) {
+                    // Already exactly this frequency: nothing to rewrite.
+                    self.nodes.clear();
+                    return Ok(());
+                }
+
+                self.nodes[last].node.header.frequency = Some(frequency);
+                if !self.words.iter().any(|existing| existing.as_slice() == word) {
+                    self.words.push(word.to_vec());
+                }
+
+                self.move_to_file(0)?;
+                self.live_bytes -= superseded;
+                self.write_header(self.merkle_len, self.merkle_root, self.autocomplete_table_len, self.bloom_len)?;
+            }
+            Descent::Diverges { remaining } => {
+                if !self.words.iter().any(|existing| existing.as_slice() == word) {
+                    self.words.push(word.to_vec());
+                }
+
+                let node_index = self.nodes.len() - 1;
+                self.add_rec(&remaining, frequency, node_index)?;
+                self.move_to_file(0)?;
+                self.live_bytes -= superseded;
+                self.write_header(self.merkle_len, self.merkle_root, self.autocomplete_table_len, self.bloom_len)?;
+            }
+            Descent::NeedsSplit => {
+                self.nodes.clear();
+                self.compacting_update(word, frequency)?;
             }
         }
 
         Ok(())
     }
+
+    /// Clear `word`'s frequency in an already-compiled file, if present. A
+    /// word that isn't in the tree (including one ending in the middle of
+    /// a compressed path) is a no-op. Like `update`, only the path from the
+    /// root down to `word` is rewritten, appended past the current end of
+    /// the file.
+    pub fn remove(&mut self, word: &[u8]) -> Result<(), String> {
+        let memory = DiskMemory::open(&self.filename, MemoryAccess::ReadOnly)?;
+        let (descent, superseded) = self.decode_path(&memory, word)?;
+        drop(memory);
+
+        let last = self.nodes.len().saturating_sub(1);
+        let found_with_frequency = match descent {
+            Descent::Found => self.nodes[last].node.header.frequency.is_some(),
+            _ => false,
+        };
+
+        if !found_with_frequency {
+            // Either `word` was never in the tree, or it already carried no
+            // frequency: nothing staged needs to be flushed.
+            self.nodes.clear();
+            return Ok(());
+        }
+
+        self.nodes[last].node.header.frequency = None;
+        self.words.retain(|existing| existing.as_slice() != word);
+
+        self.move_to_file(0)?;
+        self.live_bytes -= superseded;
+        self.write_header(self.merkle_len, self.merkle_root, self.autocomplete_table_len, self.bloom_len)?;
+
+        Ok(())
+    }
+
+    /// Decode the on-disk path `word` currently follows into the RAM
+    /// staging shape `add_rec` already understands (one byte per level, any
+    /// compressed path pulled apart into a trivial single-child chain),
+    /// stopping as soon as `word` is fully resolved or diverges. Every
+    /// sibling subtree not on this path keeps its existing on-disk offset,
+    /// copied over as-is.
+    ///
+    /// Returns how the descent ended and the total size in bytes of every
+    /// on-disk node it decoded: all of them are about to be superseded by a
+    /// fresh version once the caller flushes `self.nodes` back to the file.
+    fn decode_path(&mut self, memory: &DiskMemory, word: &[u8]) -> Result<(Descent, usize), String> {
+        self.nodes.clear();
+
+        let mut superseded = 0;
+        let mut offset = self.root;
+        let mut remaining = word;
+
+        loop {
+            let node = node_at(memory, offset).map_err(|error| format!("{:?}", error))?;
+            let header = node.header();
+            superseded += node_size(&node);
+
+            let path_length = header.path_length as usize;
+            let common = min(remaining.len(), path_length);
+            let matches = (0..common).all(|i| header.path[i] == remaining[i]);
+
+            if !matches || remaining.len() < path_length {
+                // A byte disagrees partway through the compressed path, or
+                // `word` itself ends in the middle of it: both require
+                // splitting that path, which isn't supported here.
+                return Ok((Descent::NeedsSplit, superseded));
+            }
+
+            // The whole compressed path is shared: decompress it into one
+            // trivial RAM level per byte, exactly undoing what
+            // `path_compression` will redo once this path is re-flushed.
+            for i in 0..path_length {
+                let next_index = self.nodes.len() + 1;
+                self.nodes.push(RAMNode {
+                    node: Node256 {
+                        header: NodeHeader {
+                            frequency: None,
+                            kind: NodeKind::Node256,
+                            nb_children: 1,
+                            path_length: 0,
+                            path: [0; 7],
+                        },
+                        pointers: [None; 256],
+                    },
+                    child: header.path[i],
+                    word_count: 0,
+                    completions: Vec::new(),
+                });
+
+                let last = self.nodes.len() - 1;
+                self.nodes[last].node.pointers[header.path[i] as usize] = NodeOffset::new(next_index);
+            }
+
+            remaining = &remaining[path_length..];
+
+            // The node's own content, decompressed (its path has just been
+            // pulled apart above), with every existing child reused as-is
+            // except the one `word` continues through.
+            let mut staged = Node256 {
+                header: NodeHeader {
+                    frequency: header.frequency,
+                    kind: NodeKind::Node256,
+                    nb_children: header.nb_children,
+                    path_length: 0,
+                    path: [0; 7],
+                },
+                pointers: [None; 256],
+            };
+
+            for (key, child_offset) in node.children() {
+                staged.pointers[key as usize] = NonZeroUsize::new(child_offset);
+            }
+
+            if remaining.is_empty() {
+                self.nodes.push(RAMNode { node: staged, child: 0, word_count: 0, completions: Vec::new() });
+                return Ok((Descent::Found, superseded));
+            }
+
+            let next_byte = remaining[0];
+            match node.child(next_byte) {
+                Some(child_offset) => {
+                    // This node's matching child is about to get its own
+                    // new version too: clear the slot `word` continues
+                    // through (it'll be restaged, not reused as-is) and
+                    // keep walking.
+                    staged.pointers[next_byte as usize] = None;
+                    self.nodes.push(RAMNode { node: staged, child: next_byte, word_count: 0, completions: Vec::new() });
+
+                    offset = child_offset;
+                    remaining = &remaining[1..];
+                }
+                None => {
+                    // A clean fork: no existing child for the next byte,
+                    // exactly the case `add_rec` already knows how to grow.
+                    self.nodes.push(RAMNode { node: staged, child: 0, word_count: 0, completions: Vec::new() });
+                    return Ok((Descent::Diverges { remaining: remaining.to_vec() }, superseded));
+                }
+            }
+        }
+    }
+}
+
+/// Sum the on-disk size of every node reachable from `index`, used to seed
+/// `ArtCompiler::live_bytes` when reopening an already-compiled file.
+fn measure_live_bytes(memory: &DiskMemory, index: usize) -> usize {
+    let node = node_at(memory, index).expect("corrupt ART node");
+    let own_size = node_size(&node);
+
+    let children_size: usize = node.children()
+        .into_iter()
+        .map(|(_, child)| measure_live_bytes(memory, child))
+        .sum();
+
+    own_size + children_size
+}
+
+/// Walk the subtree rooted at `index`, collecting every `(word, frequency)`
+/// pair it contains. Used to seed `ArtCompiler::words` when reopening an
+/// already-compiled file, and to gather every entry for a compacting
+/// rebuild.
+fn collect_entries(memory: &DiskMemory, index: usize) -> Vec<(Vec<u8>, WordFrequency)> {
+    let mut entries = Vec::new();
+    collect_entries_rec(memory, index, Vec::new(), &mut entries);
+    entries
+}
+
+fn collect_entries_rec(memory: &DiskMemory, index: usize, mut word: Vec<u8>, out: &mut Vec<(Vec<u8>, WordFrequency)>) {
+    let node = node_at(memory, index).expect("corrupt ART node");
+    let header = node.header();
+    word.extend_from_slice(&header.path[0..header.path_length as usize]);
+
+    if let Some(frequency) = header.frequency {
+        out.push((word.clone(), frequency));
+    }
+
+    for (key, child) in node.children() {
+        let mut child_word = word.clone();
+        child_word.push(key);
+        collect_entries_rec(memory, child, child_word, out);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::num::NonZeroU32;
+    use crate::WordData;
+    use crate::art::ArtSearch;
+    use crate::distance::{DamerauLevenshteinDistance, IncrementalDistance};
+
+    /// Mirrors the scenario the incremental edit path exists for: a handful
+    /// of `update`s pile up superseded node versions as dead space until the
+    /// next `build` decides the ratio is worth a full compacting rewrite
+    /// (see `Compiler::build`'s `appending` branch), after which the file
+    /// must still answer exactly as if it had been built fresh with the
+    /// final frequencies.
+    #[test]
+    fn update_across_the_compaction_threshold_then_rebuilds_correctly() {
+        let path = format!("/tmp/art_compiler_test_{}.bin", std::process::id());
+
+        let mut compiler = ArtCompiler::new(&path).unwrap();
+        for word in ["test", "a", "b", "other"].iter() {
+            compiler.add(word.as_bytes(), NonZeroU32::new(1).unwrap());
+        }
+        compiler.build();
+
+        // A threshold low enough that even a single `update`'s superseded
+        // root-to-leaf path crosses it, forcing `build` down the `compact`
+        // path below.
+        let mut compiler = ArtCompiler::open_existing_with_threshold(&path, 0.01).unwrap();
+        compiler.update(b"test", NonZeroU32::new(5).unwrap()).unwrap();
+        compiler.update(b"other", NonZeroU32::new(2).unwrap()).unwrap();
+        compiler.remove(b"b").unwrap();
+        compiler.build();
+
+        let trie = ArtSearch::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let mut levenshtein = DamerauLevenshteinDistance::new(&[]);
+        levenshtein.reset(b"test");
+        let results: Vec<WordData> = trie.search_top_k(&mut levenshtein, 0, 5).collect();
+
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].word, b"test");
+        assert_eq!(results[0].frequency, NonZeroU32::new(5).unwrap());
+
+        levenshtein.reset(b"b");
+        let results: Vec<WordData> = trie.search_top_k(&mut levenshtein, 0, 5).collect();
+        assert!(results.is_empty(), "removed word must not be found after the compacting rebuild");
+
+        levenshtein.reset(b"a");
+        let results: Vec<WordData> = trie.search_top_k(&mut levenshtein, 0, 5).collect();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].word, b"a");
+    }
 }
\ No newline at end of file
@@ -0,0 +1,101 @@
+//! A balanced Merkle tree over every node's on-disk bytes (see
+//! `ArtCompiler::new_with_merkle`), letting a reader detect which nodes of a
+//! compiled file have been corrupted, or confirm two compiled files hold the
+//! byte-identical dictionary by comparing a single root hash instead of
+//! diffing the whole file.
+//!
+//! Leaf `i` is the hash of the `i`-th node actually written to the file (a
+//! node reused through `dedup`'s hash-consing isn't written again, so it
+//! isn't rehashed either), in file order: the root, written last into its
+//! reserved slot, is leaf 0. Internal node `i` is the hash of its two children
+//! concatenated; a leaf count that isn't a power of two is padded by
+//! duplicating the last leaf, the common fixup for an unbalanced tree.
+
+use blake2::{Blake2s256, Digest};
+
+use core::convert::TryInto;
+
+/// The size in bytes of every hash in the tree, leaf or internal.
+pub(super) const HASH_SIZE: usize = 32;
+
+/// Hash a single node's serialized bytes into a leaf.
+pub(super) fn hash_leaf(bytes: &[u8]) -> [u8; HASH_SIZE] {
+    let mut hasher = Blake2s256::new();
+    hasher.update(bytes);
+    hasher.finalize().into()
+}
+
+fn hash_internal(left: &[u8; HASH_SIZE], right: &[u8; HASH_SIZE]) -> [u8; HASH_SIZE] {
+    let mut hasher = Blake2s256::new();
+    hasher.update(left);
+    hasher.update(right);
+    hasher.finalize().into()
+}
+
+/// Build every level above `leaves`, returning every hash computed
+/// (including `leaves` themselves) concatenated bottom layer first, with
+/// the root alone as the last entry. An empty `leaves` (a file holding no
+/// node, which can't actually happen since the root is always written) is
+/// treated as a single all-zero root so callers don't need a special case.
+pub(super) fn build(leaves: Vec<[u8; HASH_SIZE]>) -> Vec<[u8; HASH_SIZE]> {
+    if leaves.is_empty() {
+        return vec![[0; HASH_SIZE]];
+    }
+
+    let mut tree = leaves.clone();
+    let mut level = leaves;
+
+    while level.len() > 1 {
+        if level.len() % 2 == 1 {
+            level.push(*level.last().unwrap());
+        }
+
+        let next: Vec<[u8; HASH_SIZE]> = level
+            .chunks(2)
+            .map(|pair| hash_internal(&pair[0], &pair[1]))
+            .collect();
+
+        tree.extend_from_slice(&next);
+        level = next;
+    }
+
+    tree
+}
+
+/// The root of an already-built tree: its very last entry.
+pub(super) fn root_of(tree: &[[u8; HASH_SIZE]]) -> [u8; HASH_SIZE] {
+    *tree.last().expect("a built tree always holds at least its root")
+}
+
+/// Serialize the full tree as a `u64` hash count followed by each hash, in
+/// the same bottom-up order `build` produced them in.
+pub(super) fn encode(tree: &[[u8; HASH_SIZE]]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(8 + tree.len() * HASH_SIZE);
+    out.extend_from_slice(&(tree.len() as u64).to_le_bytes());
+
+    for hash in tree {
+        out.extend_from_slice(hash);
+    }
+
+    out
+}
+
+/// The inverse of `encode`.
+pub(super) fn decode(bytes: &[u8]) -> Vec<[u8; HASH_SIZE]> {
+    if bytes.len() < 8 {
+        return Vec::new();
+    }
+
+    let count = u64::from_le_bytes(bytes[0..8].try_into().unwrap()) as usize;
+    let mut tree = Vec::with_capacity(count);
+
+    let mut cursor = 8;
+    for _ in 0..count {
+        let mut hash = [0u8; HASH_SIZE];
+        hash.copy_from_slice(&bytes[cursor..cursor + HASH_SIZE]);
+        tree.push(hash);
+        cursor += HASH_SIZE;
+    }
+
+    tree
+}
@@ -5,9 +5,14 @@
 use crate::memory::DiskMemory;
 use crate::WordFrequency;
 
+mod autocomplete;
+mod merkle;
+pub mod codec;
 pub mod compiler;
 pub mod searcher;
 
+pub use autocomplete::AutocompleteConfig;
+pub use codec::{NodeDecoder, NodeEncoder};
 pub use compiler::ArtCompiler;
 pub use searcher::ArtSearch;
 
@@ -19,7 +24,7 @@ use core::num::NonZeroUsize;
 /// and placing them all here will make them have
 /// the same size as the largest one, defeating
 /// the purpose of having multiple nodes (saving space).
-#[derive(PartialEq, Eq)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
 enum NodeKind {
     Node0,
     Node4,
@@ -63,6 +68,80 @@ struct NodeHeader {
 /// while still using the same space.
 pub type NodeOffset = NonZeroUsize;
 
+/// The magic bytes that every compiled ART file starts with, so that a file
+/// that is not one (or a truncated one) is rejected on open instead of being
+/// mapped and read as garbage.
+const MAGIC: [u8; 7] = *b"DDTRIE\0";
+
+/// The on-disk layout version. It must be bumped whenever a node struct
+/// `#[repr(C)]` size or field order changes, so that a file produced by an
+/// incompatible build is refused rather than silently misread.
+const FORMAT_VERSION: u8 = 4;
+
+/// The fixed-size header written at offset 0 of every compiled ART file.
+/// All node offsets are relative to the end of this header, so the header can
+/// grow in a future version without invalidating the stored offsets.
+#[repr(C)]
+struct Header {
+    /// The magic bytes identifying a compiled ART, see `MAGIC`.
+    magic: [u8; 7],
+    /// The layout version, see `FORMAT_VERSION`.
+    version: u8,
+    /// The offset of the root node, relative to the end of the header.
+    /// Zero after a fresh build; a file edited through
+    /// `ArtCompiler::open_existing` appends a new root and records it here.
+    root: usize,
+    /// The total number of nodes stored in the file.
+    node_count: usize,
+    /// The size in bytes of the serialized Bloom filter appended at the very
+    /// end of the file, covering every word in the dictionary. Zero when no
+    /// filter was embedded (e.g. a file rebuilt from the portable format),
+    /// in which case the searcher falls back to always walking the tree.
+    bloom_len: usize,
+    /// The size in bytes of the serialized prefix→top-k autocomplete side
+    /// table (see `art::autocomplete`), appended right before the Bloom
+    /// filter. Zero when `ArtCompiler::new_with_autocomplete` wasn't used,
+    /// in which case `search_prefix` always falls back to walking the
+    /// reached subtree.
+    autocomplete_len: usize,
+    /// The size in bytes of the serialized Merkle tree (see `art::merkle`)
+    /// appended right before the autocomplete table. Zero when
+    /// `ArtCompiler::new_with_merkle` wasn't used (or the file was since
+    /// edited through `ArtCompiler::open_existing`), in which case
+    /// `ArtSearch::verify` has nothing to check against.
+    merkle_len: usize,
+    /// The Merkle root recorded at compile time, letting two parties
+    /// compare dictionaries without reading either one's node region.
+    /// Meaningless (all zero) when `merkle_len` is zero.
+    merkle_root: [u8; merkle::HASH_SIZE],
+}
+
+/// How many bytes the header occupies before the first node.
+const HEADER_SIZE: usize = core::mem::size_of::<Header>();
+
+/// Read and validate the header at the start of the memory, returning a
+/// descriptive error on a wrong magic or an unsupported version.
+fn read_header(memory: &DiskMemory) -> Result<&Header, String> {
+    if memory.len() < HEADER_SIZE {
+        return Err(String::from("File is too small to contain an ART header"));
+    }
+
+    let header = unsafe { &*(memory.data() as *const Header) };
+
+    if header.magic != MAGIC {
+        return Err(String::from("Not a compiled ART: wrong magic bytes"));
+    }
+
+    if header.version != FORMAT_VERSION {
+        return Err(format!(
+            "Unsupported ART format version {} (expected {})",
+            header.version, FORMAT_VERSION
+        ));
+    }
+
+    Ok(header)
+}
+
 /// A leaf node containing zero children.
 /// This node is usefull as for the provided dictionnary,
 /// more than 90% of the words end up in a lead node
@@ -129,9 +208,146 @@ struct Node256 {
 }
 
 unsafe fn get<T: Sized>(memory: &DiskMemory, offset: usize) -> &T {
-    debug_assert!(offset + core::mem::size_of::<T>() <= memory.len());
+    // Offsets are relative to the end of the header, so skip it here.
+    debug_assert!(HEADER_SIZE + offset + core::mem::size_of::<T>() <= memory.len());
+
+    &*(memory.data().add(HEADER_SIZE + offset) as *const T)
+}
 
-    &*(memory.data().add(offset) as *const T)
+/// A validated, typed reference to a node living in the mapped memory.
+/// Obtained through `node_at`, which guarantees the bytes are in bounds and
+/// the kind byte is a known one before the typed reference is formed.
+pub enum NodeRef<'a> {
+    Node0(&'a Node0),
+    Node4(&'a Node4),
+    Node16(&'a Node16),
+    Node48(&'a Node48),
+    Node256(&'a Node256),
+}
+
+/// The reason a node could not be read from the mapped memory.
+#[derive(Debug)]
+pub enum ParseError {
+    /// The node would extend past the end of the mapped file.
+    OutOfBounds { offset: usize, needed: usize, len: usize },
+    /// The kind byte does not name any known node kind.
+    UnknownKind { offset: usize, kind: u8 },
+}
+
+impl<'a> NodeRef<'a> {
+    /// The header shared by every node kind.
+    fn header(&self) -> &'a NodeHeader {
+        match self {
+            NodeRef::Node0(node) => &node.header,
+            NodeRef::Node4(node) => &node.header,
+            NodeRef::Node16(node) => &node.header,
+            NodeRef::Node48(node) => &node.header,
+            NodeRef::Node256(node) => &node.header,
+        }
+    }
+
+    /// The children of the node as `(key, offset)` pairs in ascending key
+    /// order, which every node layout already stores the children in.
+    fn children(&self) -> Vec<(u8, usize)> {
+        match self {
+            NodeRef::Node0(_) => Vec::new(),
+            NodeRef::Node4(node) => (0..node.header.nb_children as usize)
+                .map(|i| (node.keys[i], node.pointers[i].unwrap().get()))
+                .collect(),
+            NodeRef::Node16(node) => (0..node.header.nb_children as usize)
+                .map(|i| (node.keys[i], node.pointers[i].unwrap().get()))
+                .collect(),
+            NodeRef::Node48(node) => (0..256)
+                .filter(|&b| node.keys[b] != core::u8::MAX)
+                .map(|b| (b as u8, node.pointers[node.keys[b] as usize].unwrap().get()))
+                .collect(),
+            NodeRef::Node256(node) => (0..256)
+                .filter_map(|b| node.pointers[b].map(|pointer| (b as u8, pointer.get())))
+                .collect(),
+        }
+    }
+
+    /// The child reached through the given key, if any.
+    fn child(&self, key: u8) -> Option<usize> {
+        match self {
+            NodeRef::Node0(_) => None,
+            NodeRef::Node4(node) => (0..node.header.nb_children as usize)
+                .find(|&i| node.keys[i] == key)
+                .map(|i| node.pointers[i].unwrap().get()),
+            NodeRef::Node16(node) => (0..node.header.nb_children as usize)
+                .find(|&i| node.keys[i] == key)
+                .map(|i| node.pointers[i].unwrap().get()),
+            NodeRef::Node48(node) => {
+                let index = node.keys[key as usize];
+                if index != core::u8::MAX {
+                    Some(node.pointers[index as usize].unwrap().get())
+                } else {
+                    None
+                }
+            },
+            NodeRef::Node256(node) => node.pointers[key as usize].map(|pointer| pointer.get()),
+        }
+    }
+}
+
+/// The serialized size in bytes of a node's concrete on-disk representation.
+pub(super) fn node_size(node: &NodeRef) -> usize {
+    match node {
+        NodeRef::Node0(_) => core::mem::size_of::<Node0>(),
+        NodeRef::Node4(_) => core::mem::size_of::<Node4>(),
+        NodeRef::Node16(_) => core::mem::size_of::<Node16>(),
+        NodeRef::Node48(_) => core::mem::size_of::<Node48>(),
+        NodeRef::Node256(_) => core::mem::size_of::<Node256>(),
+    }
+}
+
+/// Read the node stored at the given offset (relative to the end of the
+/// header), validating that it stays in bounds and that its kind byte is known
+/// before forming the typed reference. This replaces the raw `get` in all of
+/// the searcher traversal so a truncated or hand-edited file is rejected
+/// instead of producing out-of-bounds reads.
+fn node_at(memory: &DiskMemory, offset: usize) -> Result<NodeRef, ParseError> {
+    use core::mem::size_of;
+
+    let base = HEADER_SIZE + offset;
+    let fits = |size: usize| base + size <= memory.len();
+
+    if !fits(size_of::<NodeHeader>()) {
+        return Err(ParseError::OutOfBounds {
+            offset,
+            needed: size_of::<NodeHeader>(),
+            len: memory.len(),
+        });
+    }
+
+    // The kind byte follows the frequency field; validate it before reading
+    // the header as a `NodeKind`, which would otherwise be undefined behavior
+    // for an out-of-range discriminant.
+    let kind_offset = size_of::<Option<WordFrequency>>();
+    let kind = unsafe { *memory.data().add(base + kind_offset) };
+    if kind > NodeKind::Node256 as u8 {
+        return Err(ParseError::UnknownKind { offset, kind });
+    }
+
+    let header = unsafe { get::<NodeHeader>(memory, offset) };
+
+    let (size, node): (usize, NodeRef) = match header.kind {
+        NodeKind::Node0 => (size_of::<Node0>(), NodeRef::Node0(unsafe { get(memory, offset) })),
+        NodeKind::Node4 => (size_of::<Node4>(), NodeRef::Node4(unsafe { get(memory, offset) })),
+        NodeKind::Node16 => (size_of::<Node16>(), NodeRef::Node16(unsafe { get(memory, offset) })),
+        NodeKind::Node48 => (size_of::<Node48>(), NodeRef::Node48(unsafe { get(memory, offset) })),
+        NodeKind::Node256 => (size_of::<Node256>(), NodeRef::Node256(unsafe { get(memory, offset) })),
+    };
+
+    if !fits(size) {
+        return Err(ParseError::OutOfBounds {
+            offset,
+            needed: size,
+            len: memory.len(),
+        });
+    }
+
+    Ok(node)
 }
 
 /// Allows to easily transform a Node256 into a Node0
@@ -280,4 +496,12 @@ mod tests {
         assert_eq!(size_of::<Node48>(), 656);
         assert_eq!(size_of::<Node256>(), 2064);
     }
+
+    #[test]
+    fn header() {
+        // 7 magic bytes + 1 version byte + five usize fields + a 32-byte
+        // Merkle root, no padding.
+        assert_eq!(size_of::<Header>(), 8 + 5 * size_of::<usize>() + 32);
+        assert_eq!(MAGIC.len(), 7);
+    }
 }
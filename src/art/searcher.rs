@@ -1,40 +1,484 @@
-use crate::{Search, Information, WordData};
+use crate::{Search, Information, Verifiable, WordData, WordFrequency};
 use crate::memory::{DiskMemory, MemoryAccess};
 use crate::distance::IncrementalDistance;
+use crate::bloom::BloomFilter;
 
-use super::{NodeKind, NodeHeader, Node0, Node4, Node16, Node48, Node256, get};
+use core::cmp::{min, Ordering};
+use std::collections::BinaryHeap;
+
+use super::{NodeRef, NodeKind, HEADER_SIZE, node_at, node_size, read_header};
+use super::codec::{self, NodeEncoder};
+use super::autocomplete;
+use super::merkle;
 
 pub struct ArtSearch {
     /// The disk memory that is been used to save all the nodes
     memory: DiskMemory,
+    /// The offset of the root node, relative to the end of the header.
+    /// Usually zero, but an incrementally-edited file (see
+    /// `ArtCompiler::open_existing`) appends a fresh root on every edit
+    /// instead of rewriting the one at offset zero in place.
+    root: usize,
+    /// How many nodes the node region holds, copied from the header. Used
+    /// only by `verify`, to know how many leaves its sequential scan of the
+    /// node region is expected to produce.
+    node_count: usize,
+    /// How many bytes, right after the header, the node region occupies
+    /// (everything before the Merkle tree/autocomplete table/Bloom filter
+    /// trailer). Used only by `verify`, to scan every node in the file
+    /// without needing to descend the tree from the root.
+    node_region_len: usize,
+    /// The dictionary's Bloom filter, appended after the node region at
+    /// compile time. `None` for a file with no embedded filter (e.g. one
+    /// rebuilt through the portable format), in which case exact lookups
+    /// always fall back to walking the tree.
+    bloom: Option<BloomFilter<Vec<u8>>>,
+    /// The encoded prefix→top-k completion table (see `art::autocomplete`),
+    /// copied out of the mapped file's trailer. Empty for a file compiled
+    /// without `ArtCompiler::new_with_autocomplete`, in which case
+    /// `prefix_search` always falls back to walking the reached subtree.
+    autocomplete: Vec<u8>,
+    /// The Merkle tree (see `art::merkle`), decoded out of the mapped
+    /// file's trailer: every hash bottom layer first, the root alone last.
+    /// Empty for a file compiled without `ArtCompiler::new_with_merkle` (or
+    /// since edited through `ArtCompiler::open_existing`), in which case
+    /// `verify`/`root_hash` report there is nothing to check.
+    merkle: Vec<[u8; merkle::HASH_SIZE]>,
+}
+
+/// The reason a prefix could not be resolved to a single word.
+#[derive(Debug, PartialEq, Eq)]
+pub enum ResolveError {
+    /// No word descends from the prefix.
+    NotFound,
+    /// More than one word descends from the prefix.
+    Multiple,
 }
 
 impl ArtSearch {
     pub fn load(filename: &str) -> Result<Self, String> {
+        let memory = DiskMemory::open(filename, MemoryAccess::ReadOnly)?;
+
+        // Reject a file that is not a compiled ART, or one produced by an
+        // incompatible layout version, before any node is read.
+        let header = read_header(&memory)?;
+
+        // The Bloom filter (if any) is the tail of the file, right after the
+        // autocomplete table; `bloom_len` tells us how many of the trailing
+        // bytes it occupies since node sizes vary and can't be derived from
+        // `node_count` alone.
+        let bloom = if header.bloom_len == 0 {
+            None
+        } else {
+            let start = memory.len() - header.bloom_len;
+            let bytes = unsafe {
+                std::slice::from_raw_parts(memory.data().add(start), header.bloom_len)
+            };
+
+            Some(BloomFilter::from(bytes))
+        };
+
+        // The autocomplete table (if any) sits right before the Bloom
+        // filter, so the Bloom filter can stay the file's absolute tail.
+        let autocomplete = if header.autocomplete_len == 0 {
+            Vec::new()
+        } else {
+            let start = memory.len() - header.bloom_len - header.autocomplete_len;
+            unsafe {
+                std::slice::from_raw_parts(memory.data().add(start), header.autocomplete_len).to_vec()
+            }
+        };
+
+        // The Merkle tree (if any) sits right before the autocomplete
+        // table, for the same reason.
+        let merkle = if header.merkle_len == 0 {
+            Vec::new()
+        } else {
+            let start = memory.len() - header.bloom_len - header.autocomplete_len - header.merkle_len;
+            let bytes = unsafe {
+                std::slice::from_raw_parts(memory.data().add(start), header.merkle_len)
+            };
+
+            merkle::decode(bytes)
+        };
+
+        let node_region_len = memory.len() - HEADER_SIZE - header.bloom_len - header.autocomplete_len - header.merkle_len;
+
         Ok(ArtSearch {
-            memory: DiskMemory::open(filename, MemoryAccess::ReadOnly)?
+            memory,
+            root: header.root,
+            node_count: header.node_count,
+            node_region_len,
+            bloom,
+            autocomplete,
+            merkle,
         })
     }
+
+    /// Whether `word` could be in the dictionary. A Bloom filter never
+    /// false-negatives, so `false` is a definitive miss; `true` only means
+    /// "maybe", including when no filter was embedded in this file.
+    pub fn might_contain(&self, word: &[u8]) -> bool {
+        match &self.bloom {
+            Some(bloom) => bloom.contains(&word.to_vec()),
+            None => true,
+        }
+    }
+
+    /// Read the node stored at the given offset, panicking if the mapped file
+    /// is corrupt. The searcher only ever follows offsets written by the
+    /// compiler, so a parse error here means the file was truncated or tampered
+    /// with and there is nothing sensible to return.
+    fn node(&self, index: usize) -> NodeRef {
+        node_at(&self.memory, index).expect("corrupt ART node")
+    }
+
+    /// Save this index through `art::codec`'s portable, explicitly
+    /// little-endian encoding instead of the raw mmap-shaped layout `load`
+    /// expects, so the file can be shipped to and loaded on a machine with
+    /// a different pointer width or endianness than the one it was
+    /// compiled on.
+    pub fn save_portable(&self, filename: &str) -> Result<(), String> {
+        let mut encoder = NodeEncoder::new();
+        let root = self.encode_portable(self.root, &mut encoder);
+        let bytes = encoder.finish(self.nodes(), root);
+
+        std::fs::write(filename, bytes)
+            .map_err(|error| format!("Can't write portable ART to \"{}\" ({})", filename, error))
+    }
+
+    /// Encode the subtree rooted at `index`, children first so their
+    /// resolved offsets are known by the time the node pointing at them is
+    /// encoded, and return the offset it landed at.
+    fn encode_portable(&self, index: usize, encoder: &mut NodeEncoder) -> usize {
+        let node = self.node(index);
+        let header = node.header();
+
+        let children: Vec<(u8, usize)> = node.children()
+            .into_iter()
+            .map(|(key, child)| (key, self.encode_portable(child, encoder)))
+            .collect();
+
+        let kind = match &node {
+            NodeRef::Node0(_) => NodeKind::Node0,
+            NodeRef::Node4(_) => NodeKind::Node4,
+            NodeRef::Node16(_) => NodeKind::Node16,
+            NodeRef::Node48(_) => NodeKind::Node48,
+            NodeRef::Node256(_) => NodeKind::Node256,
+        };
+
+        encoder.encode(kind, header.frequency, &header.path[0..header.path_length as usize], &children)
+    }
+
+    /// Load a file written by `save_portable`. The portable bytes are
+    /// decoded and rebuilt into the native mmap-shaped layout at
+    /// `native_filename`, which is then mapped through the ordinary `load`,
+    /// so this is the one path that actually bridges two platforms: it can
+    /// run on different hardware than the one `save_portable` ran on.
+    pub fn load_portable(portable_filename: &str, native_filename: &str) -> Result<Self, String> {
+        let bytes = std::fs::read(portable_filename)
+            .map_err(|error| format!("Can't read portable ART \"{}\" ({})", portable_filename, error))?;
+
+        let native = codec::rebuild_native(&bytes)?;
+
+        std::fs::write(native_filename, native)
+            .map_err(|error| format!("Can't write native ART to \"{}\" ({})", native_filename, error))?;
+
+        Self::load(native_filename)
+    }
 }
 
 impl Search for ArtSearch {
     fn search(&self, distance: &mut IncrementalDistance, max_distance: usize) -> Box<dyn Iterator<Item=WordData>> {
         if max_distance == 0 {
-            Box::new(self.exact_search(0, distance.word(), distance.word()).into_iter())
+            // The Bloom filter guarantees no false negatives, so a negative
+            // answer here is a definitive miss and saves a full
+            // root-to-leaf descent for the exact-match lookup below.
+            if !self.might_contain(distance.word()) {
+                return Box::new(None.into_iter());
+            }
+
+            Box::new(self.exact_search(self.root, distance.word(), distance.word()).into_iter())
         } else {
             let mut result = Vec::new();
 
-            self.distance_search(0, distance, max_distance, &mut result);
+            self.distance_search(self.root, distance, max_distance, &mut result);
 
             result.sort();
             Box::new(result.into_iter())
         }
     }
+
+    fn search_prefix(&self, prefix: &[u8], k: usize) -> Box<dyn Iterator<Item=WordData>> {
+        Box::new(self.prefix_search(prefix, k).into_iter())
+    }
+}
+
+impl Verifiable for ArtSearch {
+    fn verify(&self) -> Result<(), Vec<usize>> {
+        if self.merkle.is_empty() {
+            return Ok(());
+        }
+
+        // The node region holds every node exactly once, back to back, and
+        // `ArtCompiler::write_fresh` records their leaf hashes in that same
+        // file order, so a sequential scan recreates the same leaf order `art::merkle::build` hashed
+        // them in without needing to descend the tree from the root (which
+        // would miss nothing, but would also re-visit a `dedup`-shared
+        // node once per parent instead of once).
+        let mut mismatched = Vec::new();
+        let mut offset = 0;
+        let mut index = 0;
+
+        while offset < self.node_region_len {
+            let node = node_at(&self.memory, offset).expect("corrupt ART node");
+            let size = node_size(&node);
+
+            let bytes = unsafe {
+                std::slice::from_raw_parts(self.memory.data().add(HEADER_SIZE + offset), size)
+            };
+
+            if self.merkle.get(index) != Some(&merkle::hash_leaf(bytes)) {
+                mismatched.push(index);
+            }
+
+            offset += size;
+            index += 1;
+        }
+
+        if mismatched.is_empty() && index == self.node_count {
+            Ok(())
+        } else {
+            Err(mismatched)
+        }
+    }
+
+    fn root_hash(&self) -> Option<[u8; merkle::HASH_SIZE]> {
+        if self.merkle.is_empty() {
+            None
+        } else {
+            Some(merkle::root_of(&self.merkle))
+        }
+    }
 }
 
 impl ArtSearch {
+    /// Enumerate every `(word, frequency)` under the given prefix, in sorted
+    /// order. The compressed-path ART is descended until the prefix is
+    /// consumed, then the reached subtree is visited in order.
+    pub fn prefix_iter(&self, prefix: &[u8]) -> Box<dyn Iterator<Item = (Vec<u8>, WordFrequency)>> {
+        let mut result = Vec::new();
+        self.prefix_collect(self.root, Vec::new(), prefix, &mut result);
+        Box::new(result.into_iter())
+    }
+
+    /// Enumerate every `(word, frequency)` whose word lies in the half-open
+    /// range `[start, end)`, in sorted order.
+    pub fn range(&self, start: &[u8], end: &[u8]) -> Box<dyn Iterator<Item = (Vec<u8>, WordFrequency)>> {
+        let mut result = Vec::new();
+        self.range_rec(self.root, Vec::new(), start, end, &mut result);
+        Box::new(result.into_iter())
+    }
+
+    /// As-you-type completion: collect every word under `prefix` (same
+    /// descent as `prefix_iter`, so a prefix ending in the middle of a
+    /// node's compressed path or longer than any stored key is handled the
+    /// same way) and return the `limit` most frequent ones, most frequent
+    /// first.
+    ///
+    /// If the file was compiled with `ArtCompiler::new_with_autocomplete`
+    /// and the landing node's subtree crossed its `min_words` threshold,
+    /// the precomputed side table answers this directly instead of walking
+    /// the subtree.
+    pub fn prefix_search(&self, prefix: &[u8], limit: usize) -> Vec<WordData> {
+        let found = match self.descend_prefix(self.root, Vec::new(), prefix) {
+            Some((index, word)) => match autocomplete::lookup(&self.autocomplete, index) {
+                // The table keeps only its own top-n, so it can answer only
+                // when that holds at least `limit` words.
+                Some(completions) if completions.len() >= limit => completions
+                    .into_iter()
+                    .map(|(suffix, frequency)| {
+                        let mut full = word.clone();
+                        full.extend(suffix);
+                        (full, frequency)
+                    })
+                    .collect(),
+                _ => {
+                    let mut found = Vec::new();
+                    self.collect_subtree(index, word, &mut found);
+                    found
+                }
+            },
+            None => Vec::new(),
+        };
+
+        let mut result: Vec<WordData> = found
+            .into_iter()
+            .map(|(word, frequency)| WordData { word, frequency, distance: 0 })
+            .collect();
+
+        // `WordData`'s order is already (distance asc, frequency desc, word
+        // asc); every candidate here shares the same (zero) distance, so
+        // this sorts by descending frequency exactly as wanted.
+        result.sort();
+        result.truncate(limit);
+
+        result
+    }
+
+    /// Resolve a prefix to the single word that descends from it.
+    ///
+    /// Returns `ResolveError::NotFound` if no word descends from the prefix, or
+    /// `ResolveError::Multiple` if the prefix is an ambiguous abbreviation of
+    /// more than one word.
+    pub fn resolve_unique(&self, prefix: &[u8]) -> Result<(Vec<u8>, WordFrequency), ResolveError> {
+        let (index, word) = match self.descend_prefix(self.root, Vec::new(), prefix) {
+            Some(landing) => landing,
+            None => return Err(ResolveError::NotFound),
+        };
+
+        let mut found = None;
+        match self.unique_in_subtree(index, word, &mut found) {
+            Ok(()) => found.ok_or(ResolveError::NotFound),
+            Err(()) => Err(ResolveError::Multiple),
+        }
+    }
+
+    /// Descend along the prefix and return the landing node together with the
+    /// bytes from the root up to (but excluding) that node's compressed path,
+    /// or `None` when the prefix diverges from the trie.
+    fn descend_prefix(&self, index: usize, word: Vec<u8>, prefix: &[u8]) -> Option<(usize, Vec<u8>)> {
+        let node = self.node(index);
+        let header = node.header();
+        let path_length = header.path_length as usize;
+
+        let common = min(prefix.len(), path_length);
+        for i in 0..common {
+            if header.path[i] != prefix[i] {
+                return None;
+            }
+        }
+
+        if prefix.len() <= path_length {
+            Some((index, word))
+        } else {
+            let remaining = &prefix[path_length..];
+            let mut base = word;
+            base.extend_from_slice(&header.path[0..path_length]);
+
+            let child = node.child(remaining[0])?;
+            base.push(remaining[0]);
+            self.descend_prefix(child, base, &remaining[1..])
+        }
+    }
+
+    /// Walk a subtree, storing the single word it contains into `found`.
+    /// Returns `Err(())` as soon as a second word is met (ambiguous prefix).
+    fn unique_in_subtree(&self, index: usize, mut word: Vec<u8>,
+                         found: &mut Option<(Vec<u8>, WordFrequency)>) -> Result<(), ()> {
+        let node = self.node(index);
+        let header = node.header();
+        word.extend_from_slice(&header.path[0..header.path_length as usize]);
+
+        if let Some(frequency) = header.frequency {
+            if found.is_some() {
+                return Err(());
+            }
+            *found = Some((word.clone(), frequency));
+        }
+
+        for (key, child) in node.children() {
+            let mut child_word = word.clone();
+            child_word.push(key);
+            self.unique_in_subtree(child, child_word, found)?;
+        }
+
+        Ok(())
+    }
+
+    /// Descend along the prefix, then collect the whole reached subtree once
+    /// the prefix is consumed. `word` holds the bytes from the root up to (but
+    /// excluding) this node's own compressed path.
+    fn prefix_collect(&self, index: usize, word: Vec<u8>, prefix: &[u8],
+                      result: &mut Vec<(Vec<u8>, WordFrequency)>) {
+        let node = self.node(index);
+        let header = node.header();
+        let path_length = header.path_length as usize;
+
+        // The prefix must agree with the part of the compressed path it covers.
+        let common = min(prefix.len(), path_length);
+        for i in 0..common {
+            if header.path[i] != prefix[i] {
+                return; // Diverges from the prefix.
+            }
+        }
+
+        if prefix.len() <= path_length {
+            // The prefix ends within this node: the whole subtree matches.
+            self.collect_subtree(index, word, result);
+        } else {
+            // The next prefix byte selects the child to follow.
+            let remaining = &prefix[path_length..];
+            let mut base = word;
+            base.extend_from_slice(&header.path[0..path_length]);
+
+            if let Some(child) = node.child(remaining[0]) {
+                base.push(remaining[0]);
+                self.prefix_collect(child, base, &remaining[1..], result);
+            }
+        }
+    }
+
+    /// Visit a whole subtree in sorted order, emitting each word it contains.
+    /// `word` holds the bytes from the root up to (but excluding) this node's
+    /// compressed path.
+    fn collect_subtree(&self, index: usize, mut word: Vec<u8>,
+                       result: &mut Vec<(Vec<u8>, WordFrequency)>) {
+        let node = self.node(index);
+        let header = node.header();
+        word.extend_from_slice(&header.path[0..header.path_length as usize]);
+
+        if let Some(frequency) = header.frequency {
+            result.push((word.clone(), frequency));
+        }
+
+        for (key, child) in node.children() {
+            let mut child_word = word.clone();
+            child_word.push(key);
+            self.collect_subtree(child, child_word, result);
+        }
+    }
+
+    /// Visit the trie in order, keeping only the words in `[start, end)`.
+    /// `word` holds the bytes from the root up to (but excluding) this node's
+    /// compressed path.
+    fn range_rec(&self, index: usize, mut word: Vec<u8>, start: &[u8], end: &[u8],
+                 result: &mut Vec<(Vec<u8>, WordFrequency)>) {
+        let node = self.node(index);
+        let header = node.header();
+        word.extend_from_slice(&header.path[0..header.path_length as usize]);
+
+        if let Some(frequency) = header.frequency {
+            if word.as_slice() >= start && word.as_slice() < end {
+                result.push((word.clone(), frequency));
+            }
+        }
+
+        for (key, child) in node.children() {
+            let mut child_word = word.clone();
+            child_word.push(key);
+            // Every word in the child subtree is >= child_word, so once that
+            // reaches the end bound every remaining (larger) child is out too.
+            if child_word.as_slice() >= end {
+                break;
+            }
+            self.range_rec(child, child_word, start, end, result);
+        }
+    }
+
     fn exact_search(&self, index: usize, word: &[u8], full_word: &[u8]) -> Option<WordData> {
-        let header = unsafe { get::<NodeHeader>(&self.memory, index) }.unwrap();
+        let node = self.node(index);
+        let header = node.header();
 
         if word.len() < header.path_length as usize {
             return None;// Current node is after the searched word
@@ -48,86 +492,23 @@ impl ArtSearch {
 
         if word.len() == header.path_length as usize {
             // Check that the node contains a data
-            if let Some(frequency) = header.frequency {
-                // Word found, returning it
-                return Some(WordData {
-                    word: full_word.into(),
-                    frequency: frequency,
-                    distance: 0
-                });
-            } else {
-                return None;// Don't contains data in it
-            }
+            return header.frequency.map(|frequency| WordData {
+                word: full_word.into(),
+                frequency,
+                distance: 0,
+            });
         }
 
         // Need to go further
-        match header.kind {
-            NodeKind::Node0 => { None /* Can't go further */},
-            NodeKind::Node4 => {
-                let node = unsafe { get::<Node4>(&self.memory, index) }.unwrap();
-
-                for i in 0..(node.header.nb_children as usize) {
-                    if node.keys[i] == word[node.header.path_length as usize] {
-                        return self.exact_search(
-                            node.pointers[i].unwrap().get(),
-                            &word[(node.header.path_length as usize + 1)..],
-                            full_word
-                        );
-                    }
-                }
-
-                None
-            },
-            NodeKind::Node16 => {
-                let node = unsafe { get::<Node16>(&self.memory, index) }.unwrap();
-
-                for i in 0..(node.header.nb_children as usize) {
-                    if node.keys[i] == word[node.header.path_length as usize] {
-                        return self.exact_search(
-                            node.pointers[i].unwrap().get(),
-                            &word[(node.header.path_length as usize + 1)..],
-                            full_word
-                        );
-                    }
-                }
-
-                None
-            },
-            NodeKind::Node48 => {
-                let node = unsafe { get::<Node48>(&self.memory, index) }.unwrap();
-
-                let new_index = node.keys[word[node.header.path_length as usize] as usize];
-                if new_index != core::u8::MAX {
-                    // Can go futher
-                    self.exact_search(
-                        node.pointers[new_index as usize].unwrap().get(),
-                        &word[(node.header.path_length as usize + 1)..],
-                        full_word
-                    )
-                } else {
-                    None
-                }
-            },
-            NodeKind::Node256 => {
-                let node = unsafe { get::<Node256>(&self.memory, index) }.unwrap();
-
-                if let Some(index) = node.pointers[word[node.header.path_length as usize] as usize] {
-                    self.exact_search(
-                        index.get(),
-                        &word[(node.header.path_length as usize + 1)..],
-                        full_word
-                    )
-                } else {
-                    None
-                }
-            }
-        }
+        let child = node.child(word[header.path_length as usize])?;
+        self.exact_search(child, &word[(header.path_length as usize + 1)..], full_word)
     }
 
     fn distance_search(&self, index: usize, distance: &mut IncrementalDistance,
                        max_distance: usize, result: &mut Vec<WordData>) {
 
-        let header = unsafe { get::<NodeHeader>(&self.memory, index) }.unwrap();
+        let node = self.node(index);
+        let header = node.header();
 
         // Compressed path adding
         for i in 0..(header.path_length as usize) {
@@ -149,84 +530,78 @@ impl ArtSearch {
             if let Some(frequency) = header.frequency {
                 result.push(WordData {
                     word: distance.current().into(),
-                    frequency: frequency,
-                    distance: new_distance
+                    frequency,
+                    distance: new_distance,
                 });
             }
         }
 
         // Going further
-        match header.kind {
-            NodeKind::Node0 => { /* Can't go further */},
-            NodeKind::Node4 => {
-                let node = unsafe { get::<Node4>(&self.memory, index) }.unwrap();
-
-                for i in 0..(node.header.nb_children as usize) {
-                    distance.push(node.keys[i]);
-                    if distance.can_continue(max_distance) {
-                        self.distance_search(
-                            node.pointers[i].unwrap().get(),
-                            distance,
-                            max_distance,
-                            result
-                        );
-                    }
-                    distance.pop();
-                }
-            },
-            NodeKind::Node16 => {
-                let node = unsafe { get::<Node16>(&self.memory, index) }.unwrap();
-
-                for i in 0..(node.header.nb_children as usize) {
-                    distance.push(node.keys[i]);
-                    if distance.can_continue(max_distance) {
-                        self.distance_search(
-                            node.pointers[i].unwrap().get(),
-                            distance,
-                            max_distance,
-                            result
-                        );
-                    }
-                    distance.pop();
-                }
-            },
-            NodeKind::Node48 => {
-                let node = unsafe { get::<Node48>(&self.memory, index) }.unwrap();
-
-                for i in 0..node.keys.len() {
-                    let new_index = node.keys[i];
-                    if new_index == core::u8::MAX {
-                        continue;// Not a pointer
-                    }
-                    distance.push(i as u8);
-                    if distance.can_continue(max_distance) {
-                        self.distance_search(
-                            node.pointers[new_index as usize].unwrap().get(),
-                            distance,
-                            max_distance,
-                            result
-                        );
-                    }
+        for (key, child) in node.children() {
+            distance.push(key);
+            if distance.can_continue(max_distance) {
+                self.distance_search(child, distance, max_distance, result);
+            }
+            distance.pop();
+        }
+
+        for _ in 0..header.path_length {
+            // Correctly pop to prevent mistakes.
+            distance.pop();
+        }
+    }
+
+    /// As-you-type fuzzy completion: `distance` must already be in prefix
+    /// mode (`IncrementalDistance::set_prefix_mode(true)`) and reset to the
+    /// query being typed. Every word that descends from a trie node whose
+    /// prefix distance drops to `max_distance` or below is a valid
+    /// completion and is returned, scored with that prefix distance.
+    ///
+    /// Unlike `distance_search`, descent into a node's subtree stops as
+    /// soon as it is accepted: every word below it is a completion, so
+    /// there is nothing left to prune for.
+    pub fn prefix_complete_search(&self, distance: &mut IncrementalDistance, max_distance: usize) -> Vec<WordData> {
+        let mut result = Vec::new();
+        self.prefix_complete_search_rec(self.root, distance, max_distance, &mut result);
+        result.sort();
+        result
+    }
+
+    fn prefix_complete_search_rec(&self, index: usize, distance: &mut IncrementalDistance,
+                                  max_distance: usize, result: &mut Vec<WordData>) {
+        let node = self.node(index);
+        let header = node.header();
+
+        // Compressed path adding
+        for i in 0..(header.path_length as usize) {
+            distance.push(header.path[i]);
+
+            if !distance.can_continue(max_distance) {
+                for _ in 0..=i {
+                    // Correctly pop to prevent mistakes.
                     distance.pop();
                 }
-            },
-            NodeKind::Node256 => {
-                let node = unsafe { get::<Node256>(&self.memory, index) }.unwrap();
-
-                for i in 0..node.pointers.len() {
-                    if let Some(index) = node.pointers[i] {
-                        distance.push(i as u8);
-                        if distance.can_continue(max_distance) {
-                            self.distance_search(
-                                index.get(),
-                                distance,
-                                max_distance,
-                                result
-                            );
-                        }
-                        distance.pop();
-                    }
+                return;
+            }
+        }
+
+        let new_distance = distance.distance();
+
+        if new_distance <= max_distance {
+            // Every word under this node is a valid completion.
+            // `current()` already ends with this node's compressed path,
+            // which `collect_completions` appends itself.
+            let current = distance.current();
+            let base = current[..current.len() - header.path_length as usize].to_vec();
+            self.collect_completions(index, base, new_distance, result);
+        } else {
+            // Going further
+            for (key, child) in node.children() {
+                distance.push(key);
+                if distance.can_continue(max_distance) {
+                    self.prefix_complete_search_rec(child, distance, max_distance, result);
                 }
+                distance.pop();
             }
         }
 
@@ -235,241 +610,351 @@ impl ArtSearch {
             distance.pop();
         }
     }
-}
 
-impl Information for ArtSearch {
-    fn words(&self) -> usize {
-        self.words_rec(0)
-    }
+    /// Collect every word under the already-accepted node at `index`,
+    /// scored with the `prefix_distance` reached at that node. `word`
+    /// holds the bytes from the root up to (but excluding) this node's
+    /// compressed path.
+    fn collect_completions(&self, index: usize, mut word: Vec<u8>, prefix_distance: usize, result: &mut Vec<WordData>) {
+        let node = self.node(index);
+        let header = node.header();
+        word.extend_from_slice(&header.path[0..header.path_length as usize]);
 
-    fn nodes(&self) -> usize {
-        self.nodes_rec(0)
-    }
+        if let Some(frequency) = header.frequency {
+            result.push(WordData {
+                word: word.clone(),
+                frequency,
+                distance: prefix_distance,
+            });
+        }
 
-    fn height(&self) -> usize {
-        self.height_rec(0)
+        for (key, child) in node.children() {
+            let mut child_word = word.clone();
+            child_word.push(key);
+            self.collect_completions(child, child_word, prefix_distance, result);
+        }
     }
 
-    fn max_lenght(&self) -> usize {
-        self.max_lenght_rec(0)
+    /// Search for the `k` closest words to the one held by `distance`,
+    /// lazily, in ranked order, without ever materializing the full
+    /// candidate set.
+    ///
+    /// This runs a best-first expansion over a `BinaryHeap` frontier: each
+    /// entry forks its own cloned automaton (via `IncrementalDistance::box_clone`)
+    /// so sibling branches don't step on each other, and is ordered by
+    /// `IncrementalDistance::lower_bound()` as of the edge leading into it —
+    /// a true lower bound on every word still reachable below it, unlike
+    /// the realized edit distance, which can decrease again as more
+    /// matching bytes are pushed and so cannot be used as a search priority.
+    /// A completed word is queued onto the same heap (scored by its own,
+    /// now-exact, distance) instead of being returned right away, so a node
+    /// still pending in the frontier still gets a chance to beat it to the
+    /// front. That makes the first `k` entries popped off the heap the
+    /// globally closest `k`, and the caller can stop early (by simply
+    /// dropping the iterator) without ever touching the rest of the trie.
+    ///
+    /// This is strictly cheaper than collecting every match under
+    /// `max_distance` into a bounded result heap and sorting it afterwards:
+    /// a node whose bound is already worse than `max_distance` is pruned
+    /// via `can_continue` before it ever reaches the frontier, so
+    /// candidates that a result-only heap would still have to visit (just
+    /// to discard) are never touched here at all. That pruning is only
+    /// sound because the bound it prunes on is admissible (see
+    /// `IncrementalDistance::lower_bound`) — pruning on the realized,
+    /// non-monotonic `distance()` instead could discard a branch that still
+    /// hides a closer match further down.
+    pub fn search_top_k<'a>(&'a self, distance: &mut IncrementalDistance, max_distance: usize, k: usize) -> Box<dyn Iterator<Item = WordData> + 'a> {
+        // The frontier rarely holds more than a handful of entries per
+        // level of the trie, but reserving `k` up front avoids reallocation
+        // churn for the common case where the caller asks for a sizeable
+        // ranked list.
+        let mut heap = BinaryHeap::with_capacity(k);
+
+        if k != 0 {
+            let automaton = distance.box_clone();
+            let frequency = self.node(self.root).header().frequency;
+            let bound = automaton.lower_bound();
+
+            heap.push(Frontier::Pending { index: self.root, automaton, bound, frequency });
+        }
+
+        Box::new(TopKSearch {
+            search: self,
+            heap,
+            max_distance,
+            remaining: k,
+        })
     }
+}
 
-    fn graph(&self) {
-        println!("digraph G {{");
+/// One entry in `search_top_k`'s best-first frontier: either a node still
+/// waiting to be expanded, or a word already found and queued to be
+/// returned once nothing still pending could possibly beat it.
+enum Frontier {
+    /// A node to expand once this state is popped.
+    Pending {
+        /// The node to expand once this state is popped.
+        index: usize,
+        /// The automaton as of the edge leading into `index` (the node's own
+        /// compressed path is pushed only once this state is popped).
+        automaton: Box<dyn IncrementalDistance>,
+        /// `automaton.lower_bound()` at the time this state was created: a
+        /// true lower bound on the distance of anything still reachable
+        /// below `index`.
+        bound: usize,
+        /// `index`'s own frequency, if it ends a word, used only to break
+        /// ties between two states sitting at the same bound.
+        frequency: Option<WordFrequency>,
+    },
+    /// A word that has already been matched, waiting its turn: queued
+    /// instead of returned immediately so a `Pending` entry still in the
+    /// heap gets the chance to produce something closer first.
+    Found(WordData),
+}
 
-        self.graph_rec(0);
+impl Frontier {
+    /// The key this entry is ordered by first: a lower bound on its distance
+    /// (exact, for an already-found word) and its frequency.
+    fn priority(&self) -> (usize, Option<WordFrequency>) {
+        match self {
+            Frontier::Pending { bound, frequency, .. } => (*bound, *frequency),
+            Frontier::Found(word) => (word.distance, Some(word.frequency)),
+        }
+    }
+}
 
-        println!("}}");
+impl PartialEq for Frontier {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
     }
 }
 
-impl ArtSearch {
-    fn words_rec(&self, index: usize) -> usize {
-        match (unsafe { get::<NodeHeader>(&self.memory, index) }).unwrap().kind {
-            NodeKind::Node0 => {
-                let node = unsafe { get::<Node0>(&self.memory, index) }.unwrap();
+impl Eq for Frontier {}
 
-                if node.header.frequency.is_some() { 1 } else { 0 }
-            },
-            NodeKind::Node4 => {
-                let node = unsafe { get::<Node4>(&self.memory, index) }.unwrap();
+impl PartialOrd for Frontier {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
 
-                let count: usize = if unsafe { &node.header.frequency }.is_some() { 1 } else { 0 };
-                let children_count: usize = (0..node.header.nb_children)
-                        .map(|index| self.words_rec(node.pointers[index as usize].unwrap().get()))
-                        .sum();
+impl Ord for Frontier {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // BinaryHeap is a max-heap, so everything is reversed to pop the
+        // lowest bound first. At an equal bound a `Pending` entry pops
+        // before a `Found` one, as it may still hold a word ranked ahead of
+        // it; two `Found` words pop in `WordData` order, and two `Pending`
+        // entries highest frequency first.
+        let (bound, frequency) = self.priority();
+        let (other_bound, other_frequency) = other.priority();
+
+        other_bound.cmp(&bound).then_with(|| match (self, other) {
+            (Frontier::Found(word), Frontier::Found(other_word)) => other_word.cmp(word),
+            (Frontier::Pending { .. }, Frontier::Found(_)) => Ordering::Greater,
+            (Frontier::Found(_), Frontier::Pending { .. }) => Ordering::Less,
+            (Frontier::Pending { .. }, Frontier::Pending { .. }) => frequency.cmp(&other_frequency),
+        })
+    }
+}
 
-                count + children_count
-            },
-            NodeKind::Node16 => {
-                let node = unsafe { get::<Node16>(&self.memory, index) }.unwrap();
+/// The lazy iterator returned by `ArtSearch::search_top_k`.
+struct TopKSearch<'a> {
+    search: &'a ArtSearch,
+    heap: BinaryHeap<Frontier>,
+    max_distance: usize,
+    remaining: usize,
+}
 
-                let count: usize = if node.header.frequency.is_some() { 1 } else { 0 };
-                let children_count: usize = (0..node.header.nb_children)
-                        .map(|index| self.words_rec(node.pointers[index as usize].unwrap().get()))
-                        .sum();
+impl<'a> Iterator for TopKSearch<'a> {
+    type Item = WordData;
 
-                count + children_count
-            },
-            NodeKind::Node48 => {
-                let node = unsafe { get::<Node48>(&self.memory, index) }.unwrap();
+    fn next(&mut self) -> Option<WordData> {
+        if self.remaining == 0 {
+            return None;
+        }
 
-                let count: usize = if node.header.frequency.is_some() { 1 } else { 0 };
-                let children_count: usize = node.keys
-                        .iter()
-                        .filter(|index| **index != core::u8::MAX)
-                        .map(|index| self.words_rec(node.pointers[*index as usize].unwrap().get()))
-                        .sum();
+        while let Some(entry) = self.heap.pop() {
+            let (index, mut automaton) = match entry {
+                Frontier::Found(word) => {
+                    self.remaining -= 1;
+                    return Some(word);
+                }
+                Frontier::Pending { index, automaton, .. } => (index, automaton),
+            };
 
-                count + children_count
-            },
-            NodeKind::Node256 => {
-                let node = unsafe { get::<Node256>(&self.memory, index) }.unwrap();
+            let node = self.search.node(index);
+            let header = node.header();
 
-                let count: usize = if node.header.frequency.is_some() { 1 } else { 0 };
-                let children_count: usize = node.pointers
-                        .iter()
-                        .filter(|index| index.is_some())
-                        .map(|index| self.words_rec(index.unwrap().get()))
-                        .sum();
+            // Push the node's own compressed path, bailing out of this
+            // branch entirely if the bound is blown along the way.
+            let mut pruned = false;
+            for i in 0..(header.path_length as usize) {
+                automaton.push(header.path[i]);
 
-                count + children_count
+                if !automaton.can_continue(self.max_distance) {
+                    pruned = true;
+                    break;
+                }
             }
-        }
-    }
 
-    fn nodes_rec(&self, index: usize) -> usize {
-        match (unsafe { get::<NodeHeader>(&self.memory, index) }).unwrap().kind {
-            NodeKind::Node0 => { 1 },
-            NodeKind::Node4 => {
-                let node = unsafe { get::<Node4>(&self.memory, index) }.unwrap();
+            if pruned {
+                continue;
+            }
 
-                let children_count: usize = (0..node.header.nb_children)
-                        .map(|index| self.nodes_rec(node.pointers[index as usize].unwrap().get()))
-                        .sum();
+            let current_distance = automaton.distance();
+            if current_distance <= self.max_distance {
+                if let Some(frequency) = header.frequency {
+                    self.heap.push(Frontier::Found(WordData {
+                        word: automaton.current().into(),
+                        frequency,
+                        distance: current_distance,
+                    }));
+                }
+            }
 
-                1 + children_count
-            },
-            NodeKind::Node16 => {
-                let node = unsafe { get::<Node16>(&self.memory, index) }.unwrap();
+            for (key, child) in node.children() {
+                let mut child_automaton = automaton.box_clone();
+                child_automaton.push(key);
 
-                let children_count: usize = (0..node.header.nb_children)
-                        .map(|index| self.nodes_rec(node.pointers[index as usize].unwrap().get()))
-                        .sum();
+                if child_automaton.can_continue(self.max_distance) {
+                    let bound = child_automaton.lower_bound();
+                    let frequency = self.search.node(child).header().frequency;
 
-                1 + children_count
-            },
-            NodeKind::Node48 => {
-                let node = unsafe { get::<Node48>(&self.memory, index) }.unwrap();
+                    self.heap.push(Frontier::Pending {
+                        index: child,
+                        automaton: child_automaton,
+                        bound,
+                        frequency,
+                    });
+                }
+            }
+        }
 
-                let children_count: usize = node.keys
-                        .iter()
-                        .filter(|index| **index != core::u8::MAX)
-                        .map(|index| self.nodes_rec(node.pointers[*index as usize].unwrap().get()))
-                        .sum();
+        None
+    }
+}
 
-                1 + children_count
-            },
-            NodeKind::Node256 => {
-                let node = unsafe { get::<Node256>(&self.memory, index) }.unwrap();
+impl Information for ArtSearch {
+    fn words(&self) -> usize {
+        self.words_rec(self.root)
+    }
 
-                let children_count: usize = node.pointers
-                        .iter()
-                        .filter(|index| index.is_some())
-                        .map(|index| self.nodes_rec(index.unwrap().get()))
-                        .sum();
+    fn nodes(&self) -> usize {
+        self.nodes_rec(self.root)
+    }
 
-                1 + children_count
-            }
-        }
+    fn height(&self) -> usize {
+        self.height_rec(self.root)
     }
 
-    fn height_rec(&self, index: usize) -> usize {
-        match (unsafe { get::<NodeHeader>(&self.memory, index) }).unwrap().kind {
-            NodeKind::Node0 => { 0 },
-            NodeKind::Node4 => {
-                let node = unsafe { get::<Node4>(&self.memory, index) }.unwrap();
+    fn max_lenght(&self) -> usize {
+        self.max_lenght_rec(self.root)
+    }
 
-                let children_count: usize = (0..node.header.nb_children)
-                        .map(|index| self.height_rec(node.pointers[index as usize].unwrap().get()))
-                        .max()
-                        .unwrap_or(0);
+    fn graph(&self) {
+        println!("digraph G {{");
 
-                1 + children_count
-            },
-            NodeKind::Node16 => {
-                let node = unsafe { get::<Node16>(&self.memory, index) }.unwrap();
+        self.graph_rec(self.root);
 
-                let children_count: usize = (0..node.header.nb_children)
-                        .map(|index| self.height_rec(node.pointers[index as usize].unwrap().get()))
-                        .max()
-                        .unwrap_or(0);
+        println!("}}");
+    }
+}
 
-                1 + children_count
-            },
-            NodeKind::Node48 => {
-                let node = unsafe { get::<Node48>(&self.memory, index) }.unwrap();
+/// A breakdown of the bytes an `ArtSearch` occupies, per `NodeKind` tier.
+/// Lets a caller see how much space goes to oversized nodes (e.g. a
+/// `Node256` holding only a couple of children) and whether a rebuild with
+/// tighter node promotion thresholds would shrink the structure.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryUsage {
+    pub node0_bytes: usize,
+    pub node4_bytes: usize,
+    pub node16_bytes: usize,
+    pub node48_bytes: usize,
+    pub node256_bytes: usize,
+}
 
-                let children_count: usize = node.keys
-                        .iter()
-                        .filter(|index| **index != core::u8::MAX)
-                        .map(|index| self.height_rec(node.pointers[*index as usize].unwrap().get()))
-                        .max()
-                        .unwrap_or(0);
+impl MemoryUsage {
+    /// The total number of bytes across every node tier.
+    pub fn total(&self) -> usize {
+        self.node0_bytes + self.node4_bytes + self.node16_bytes + self.node48_bytes + self.node256_bytes
+    }
+}
 
-                1 + children_count
-            },
-            NodeKind::Node256 => {
-                let node = unsafe { get::<Node256>(&self.memory, index) }.unwrap();
+impl ArtSearch {
+    /// Recurse the tree (like `nodes_rec`) to report how many bytes are
+    /// spent on each `NodeKind` tier.
+    pub fn memory_usage(&self) -> MemoryUsage {
+        let mut usage = MemoryUsage::default();
+        self.memory_usage_rec(self.root, &mut usage);
+        usage
+    }
 
-                let children_count: usize = node.pointers
-                        .iter()
-                        .filter(|index| index.is_some())
-                        .map(|index| self.height_rec(index.unwrap().get()))
-                        .max()
-                        .unwrap_or(0);
+    fn memory_usage_rec(&self, index: usize, usage: &mut MemoryUsage) {
+        use core::mem::size_of;
 
-                1 + children_count
-            }
+        let node = self.node(index);
+
+        match &node {
+            NodeRef::Node0(_) => usage.node0_bytes += size_of::<super::Node0>(),
+            NodeRef::Node4(_) => usage.node4_bytes += size_of::<super::Node4>(),
+            NodeRef::Node16(_) => usage.node16_bytes += size_of::<super::Node16>(),
+            NodeRef::Node48(_) => usage.node48_bytes += size_of::<super::Node48>(),
+            NodeRef::Node256(_) => usage.node256_bytes += size_of::<super::Node256>(),
         }
-    }
 
-    fn max_lenght_rec(&self, index: usize) -> usize {
-        match (unsafe { get::<NodeHeader>(&self.memory, index) }).unwrap().kind {
-            NodeKind::Node0 => {
-                let node = unsafe { get::<Node0>(&self.memory, index) }.unwrap();
+        for (_, child) in node.children() {
+            self.memory_usage_rec(child, usage);
+        }
+    }
+}
 
-                1 + node.header.path_length as usize
-            },
-            NodeKind::Node4 => {
-                let node = unsafe { get::<Node4>(&self.memory, index) }.unwrap();
+impl ArtSearch {
+    fn words_rec(&self, index: usize) -> usize {
+        let node = self.node(index);
 
-                let children_count: usize = (0..node.header.nb_children)
-                        .map(|index| self.max_lenght_rec(node.pointers[index as usize].unwrap().get()))
-                        .max()
-                        .unwrap_or(0);
+        let count: usize = if node.header().frequency.is_some() { 1 } else { 0 };
+        let children_count: usize = node.children()
+                .into_iter()
+                .map(|(_, child)| self.words_rec(child))
+                .sum();
 
-                1 + node.header.path_length as usize + children_count
-            },
-            NodeKind::Node16 => {
-                let node = unsafe { get::<Node16>(&self.memory, index) }.unwrap();
+        count + children_count
+    }
 
-                let children_count: usize = (0..node.header.nb_children)
-                        .map(|index| self.max_lenght_rec(node.pointers[index as usize].unwrap().get()))
-                        .max()
-                        .unwrap_or(0);
+    fn nodes_rec(&self, index: usize) -> usize {
+        let children_count: usize = self.node(index).children()
+                .into_iter()
+                .map(|(_, child)| self.nodes_rec(child))
+                .sum();
 
-                1 + node.header.path_length as usize + children_count
-            },
-            NodeKind::Node48 => {
-                let node = unsafe { get::<Node48>(&self.memory, index) }.unwrap();
+        1 + children_count
+    }
 
-                let children_count: usize = node.keys
-                        .iter()
-                        .filter(|index| **index != core::u8::MAX)
-                        .map(|index| self.max_lenght_rec(node.pointers[*index as usize].unwrap().get()))
-                        .max()
-                        .unwrap_or(0);
+    fn height_rec(&self, index: usize) -> usize {
+        match self.node(index).children()
+                .into_iter()
+                .map(|(_, child)| self.height_rec(child))
+                .max() {
+            Some(children_height) => 1 + children_height,
+            None => 0,
+        }
+    }
 
-                1 + node.header.path_length as usize + children_count
-            },
-            NodeKind::Node256 => {
-                let node = unsafe { get::<Node256>(&self.memory, index) }.unwrap();
+    fn max_lenght_rec(&self, index: usize) -> usize {
+        let node = self.node(index);
+        let path_length = node.header().path_length as usize;
 
-                let children_count: usize = node.pointers
-                        .iter()
-                        .filter(|index| index.is_some())
-                        .map(|index| self.max_lenght_rec(index.unwrap().get()))
-                        .max()
-                        .unwrap_or(0);
+        let children_count: usize = node.children()
+                .into_iter()
+                .map(|(_, child)| self.max_lenght_rec(child))
+                .max()
+                .unwrap_or(0);
 
-                1 + node.header.path_length as usize + children_count
-            }
-        }
+        1 + path_length + children_count
     }
 
     fn graph_rec_display_link(&self, index: usize, child_index: usize, value: char) {
-        let child_header = unsafe { get::<NodeHeader>(&self.memory, child_index) }.unwrap();
+        let child = self.node(child_index);
+        let child_header = child.header();
 
         println!("{} -> {} [label=\"{}{}\"];",
             index, child_index, value,
@@ -478,7 +963,8 @@ impl ArtSearch {
     }
 
     fn graph_rec(&self, index: usize) {
-        let header = unsafe { get::<NodeHeader>(&self.memory, index) }.unwrap();
+        let node = self.node(index);
+        let header = node.header();
 
         print!("{} [", index);
 
@@ -488,64 +974,19 @@ impl ArtSearch {
             print!("label=\"\"");
         }
 
-        print!(", shape={}", match header.kind {
-            NodeKind::Node0 => "circle",
-            NodeKind::Node4 => "triangle",
-            NodeKind::Node16 => "box",
-            NodeKind::Node48 => "pentagon",
-            NodeKind::Node256 => "hexagon",
+        print!(", shape={}", match &node {
+            NodeRef::Node0(_) => "circle",
+            NodeRef::Node4(_) => "triangle",
+            NodeRef::Node16(_) => "box",
+            NodeRef::Node48(_) => "pentagon",
+            NodeRef::Node256(_) => "hexagon",
         });
 
         println!("];");
 
-        match (unsafe { get::<NodeHeader>(&self.memory, index) }).unwrap().kind {
-            NodeKind::Node0 => { /* No more thing to do */ },
-            NodeKind::Node4 => {
-                let node = unsafe { get::<Node4>(&self.memory, index) }.unwrap();
-
-                for (value, child_index) in (0..node.header.nb_children)
-                                                .map(|index| (node.keys[index as usize] as char, node.pointers[index as usize].unwrap())) {
-
-                    self.graph_rec_display_link(index, child_index.get(), value);
-                    self.graph_rec(child_index.get());
-                }
-            },
-            NodeKind::Node16 => {
-                let node = unsafe { get::<Node16>(&self.memory, index) }.unwrap();
-
-                for (value, child_index) in (0..node.header.nb_children)
-                                                .map(|index| (node.keys[index as usize] as char, node.pointers[index as usize].unwrap())) {
-
-                    self.graph_rec_display_link(index, child_index.get(), value);
-                    self.graph_rec(child_index.get());
-                }
-            },
-            NodeKind::Node48 => {
-                let node = unsafe { get::<Node48>(&self.memory, index) }.unwrap();
-
-                for (value, child_index) in node.keys
-                                                .iter()
-                                                .enumerate()
-                                                .filter(|(_, ptr_index)| **ptr_index != core::u8::MAX)
-                                                .map(|(index, ptr)| (index as u8 as char, node.pointers[*ptr as usize].unwrap())) {
-
-                    self.graph_rec_display_link(index, child_index.get(), value);
-                    self.graph_rec(child_index.get());
-                }
-            },
-            NodeKind::Node256 => {
-                let node = unsafe { get::<Node256>(&self.memory, index) }.unwrap();
-
-                for (value, child_index) in node.pointers
-                                       .iter()
-                                       .enumerate()
-                                       .filter(|(_, index)| index.is_some())
-                                       .map(|(value, index)| (value as u8 as char, index.unwrap())) {
-
-                    self.graph_rec_display_link(index, child_index.get(), value);
-                    self.graph_rec(child_index.get());
-                }
-            }
+        for (value, child_index) in node.children() {
+            self.graph_rec_display_link(index, child_index, value as char);
+            self.graph_rec(child_index);
         }
     }
-}
\ No newline at end of file
+}
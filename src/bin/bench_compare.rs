@@ -0,0 +1,82 @@
+extern crate trie;
+
+use std::time::Instant;
+
+use trie::art::ArtSearch;
+use trie::trie::TrieSearch;
+use trie::dictionary::{Dictionary, DictionaryLine};
+use trie::distance::{DamerauLevenshteinDistance, IncrementalDistance};
+use trie::{Information, Search};
+
+/// Time one query for every `word` in `lines` against `search`, in queries/sec,
+/// at the given `max_distance`, and return the amount of matched words so
+/// the caller can sanity check both backends agree.
+fn bench_backend(
+    name: &str,
+    search: &dyn Search,
+    lines: &[DictionaryLine],
+    max_distance: usize,
+) -> usize {
+    let mut distance = DamerauLevenshteinDistance::new(&[]);
+    let mut found = 0;
+
+    let start = Instant::now();
+    for line in lines.iter() {
+        distance.reset(line.word.as_bytes());
+        found += search.search(&mut distance, max_distance).count();
+    }
+    let elapsed = start.elapsed();
+
+    let query_per_sec = lines.len() as u128 * 1000 / std::cmp::max(1, elapsed.as_millis());
+
+    println!(
+        "{}: distance {}, {} ms for {} queries => {} query/sec, {} matches",
+        name,
+        max_distance,
+        elapsed.as_millis(),
+        lines.len(),
+        query_per_sec,
+        found
+    );
+
+    found
+}
+
+fn main() {
+    let art_filename = std::env::args()
+        .nth(1)
+        .expect("Missing compiled ART file as first argument");
+    let trie_filename = std::env::args()
+        .nth(2)
+        .expect("Missing compiled reference trie file as second argument");
+    let query_filename = std::env::args()
+        .nth(3)
+        .expect("Missing query dictionary filename as third argument");
+
+    let art = ArtSearch::load(&art_filename).unwrap();
+    let reference = TrieSearch::load(&trie_filename).unwrap();
+
+    let lines: Vec<DictionaryLine> = Dictionary::new(&query_filename)
+        .expect("Could not load query dictionary file")
+        .into_iter()
+        .collect();
+
+    println!("art: words {}, nodes {}, height {}, max_lenght {}",
+        art.words(), art.nodes(), art.height(), art.max_lenght());
+    let memory_usage = art.memory_usage();
+    println!("art: memory_usage {} bytes (node0 {}, node4 {}, node16 {}, node48 {}, node256 {})",
+        memory_usage.total(),
+        memory_usage.node0_bytes,
+        memory_usage.node4_bytes,
+        memory_usage.node16_bytes,
+        memory_usage.node48_bytes,
+        memory_usage.node256_bytes);
+
+    println!("trie: words {}, nodes {}, height {}, max_lenght {}",
+        reference.words(), reference.nodes(), reference.height(), reference.max_lenght());
+
+    for max_distance in [0, 1, 2].iter() {
+        bench_backend("art", &art, &lines, *max_distance);
+        bench_backend("trie", &reference, &lines, *max_distance);
+    }
+}
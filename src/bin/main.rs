@@ -23,16 +23,28 @@ fn basic_test() {
 
     let trie = ArtSearch::load("test.bin").unwrap();
     let mut levenshtein = DamerauLevenshteinDistance::new(&[]);
-    let mut results = Vec::<WordData>::new();
+
+    // Top 5 best-ranked suggestions are enough for this smoke test; the
+    // heap-bounded `search_top_k` stops descending as soon as it can prove
+    // no better candidate remains, instead of collecting every match under
+    // `distance` and sorting them afterwards.
+    const TOP_K: usize = 5;
 
     for distance in [0, 1, 2, 3, 4].iter() {
         for word in ["test", "a", "b", "other", "ab"].iter() {
             println!("Searching {}, distance {}", word, distance);
 
             levenshtein.reset(word.as_bytes());
-            results.clear();
 
-            trie.search(&mut levenshtein, *distance, &mut results);
+            let results: Vec<WordData> = trie.search_top_k(&mut levenshtein, *distance, TOP_K).collect();
+
+            assert!(
+                results.windows(2).all(|pair| pair[0] <= pair[1]),
+                "search_top_k must yield results already in ranked order (increasing \
+                 distance, then decreasing frequency) for {:?} at distance {}",
+                word,
+                distance
+            );
 
             for word_data in results.iter() {
                 println!(
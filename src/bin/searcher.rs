@@ -1,14 +1,52 @@
 extern crate trie;
 
 use core::str::from_utf8_unchecked;
+use std::collections::HashMap;
 use std::io::{self, BufRead, StdoutLock, Write};
 
 use trie::distance::{
     DamerauLevenshteinBitDistance, DamerauLevenshteinDistance, IncrementalDistance,
 };
 use trie::limit::Limit;
+use trie::lru_cache::LruCache;
 use trie::{art::ArtSearch, Search, WordData};
 
+/// How many distinct `(word, distance, mode)` queries the result cache
+/// keeps around. A UI that re-sends the query as the user edits a single
+/// word rarely needs more than a few dozen in flight at once, so this
+/// stays well under the process' `Limit::Memory` budget.
+const CACHE_CAPACITY: usize = 256;
+
+/// The part of a request besides the word itself: the `max_distance` and
+/// which of `search`/`search_top_k`/`prefix_complete_search` answers it, so
+/// that differently-shaped requests for the same word are cached apart.
+#[derive(PartialEq, Eq, Hash, Clone, Copy)]
+enum QueryMode {
+    Search,
+    TopK(usize),
+    Prefix,
+}
+
+/// Assigns small, stable integer ids to query words as they are first seen,
+/// so the result cache can be keyed on a cheap `u32` instead of cloning the
+/// word into every cache entry.
+#[derive(Default)]
+struct WordInterner {
+    ids: HashMap<Vec<u8>, u32>,
+}
+
+impl WordInterner {
+    fn intern(&mut self, word: &[u8]) -> u32 {
+        if let Some(&id) = self.ids.get(word) {
+            return id;
+        }
+
+        let id = self.ids.len() as u32;
+        self.ids.insert(word.into(), id);
+        id
+    }
+}
+
 fn write_word_data(stdout: &mut StdoutLock, data: &WordData) {
     let word = unsafe { from_utf8_unchecked(&data.word) };
 
@@ -43,33 +81,70 @@ fn main() {
 
     let mut results = Vec::<WordData>::new();
 
+    let mut interner = WordInterner::default();
+    let mut cache = LruCache::<(u32, usize, QueryMode), Vec<WordData>>::new(CACHE_CAPACITY);
+
     while let Ok(nb_read) = stdin.read_line(&mut line) {
         if nb_read == 0 {
             break; // End of file, nothing more to read.
         }
 
         let mut words = line.split_whitespace();
-        let max_distance = str::parse(
+        let word = words
+            .next()
+            .expect("Expected the word to search as first argument");
+        let max_distance: usize = str::parse(
             words
-                .nth(1)
+                .next()
                 .expect("Expected a second argument: the distance"),
         )
         .expect("The distance is not a number");
-
-        let word = words
-            .next()
-            .expect("Expected the word to search as last argument");
+        // An optional third argument switches to as-you-type completion:
+        // `word` is then treated as a fuzzy prefix instead of a whole word.
+        let is_prefix = words.next() == Some("prefix");
+        // An optional fourth argument bounds the amount of ranked
+        // suggestions returned, via the heap-bounded `search_top_k`
+        // instead of collecting every match under `max_distance`.
+        let top_k: Option<usize> = words.next().map(|value| {
+            str::parse(value).expect("The top-k count is not a number")
+        });
 
         let word = word.as_bytes();
 
-        // Takes the optmized version of levenshtein if it can.
-        if leveinshtein_bit.allows(word, max_distance) {
-            leveinshtein_bit.reset(word);
-            searcher.search(&mut leveinshtein_bit, max_distance, &mut results);
+        let mode = if is_prefix {
+            QueryMode::Prefix
+        } else if let Some(k) = top_k {
+            QueryMode::TopK(k)
         } else {
-            leveinshtein.reset(word);
-            searcher.search(&mut leveinshtein, max_distance, &mut results);
+            QueryMode::Search
         };
+        let cache_key = (interner.intern(word), max_distance, mode);
+
+        results.clear();
+        if mode == QueryMode::Search && max_distance == 0 && !searcher.might_contain(word) {
+            // Definitive miss straight from the embedded Bloom filter:
+            // skip both the cache and the trie entirely.
+        } else if let Some(cached) = cache.get(&cache_key) {
+            results.extend(cached.iter().cloned());
+        } else {
+            if is_prefix {
+                leveinshtein.set_prefix_mode(true);
+                leveinshtein.reset(word);
+                results.extend(searcher.prefix_complete_search(&mut leveinshtein, max_distance));
+                leveinshtein.set_prefix_mode(false);
+            } else if let Some(k) = top_k {
+                leveinshtein.reset(word);
+                results.extend(searcher.search_top_k(&mut leveinshtein, max_distance, k));
+            } else if leveinshtein_bit.allows(word, max_distance) {
+                leveinshtein_bit.reset(word);
+                results.extend(searcher.search(&mut leveinshtein_bit, max_distance));
+            } else {
+                leveinshtein.reset(word);
+                results.extend(searcher.search(&mut leveinshtein, max_distance));
+            };
+
+            cache.put(cache_key, results.clone());
+        }
 
         line.clear(); // To prevent reading the same line again and again
         write!(stdout, "[").unwrap();
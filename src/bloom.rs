@@ -116,6 +116,15 @@ impl <T: Hashable> From<&[u8]> for BloomFilter<T> {
     }
 }
 
+/// Words are owned byte buffers wherever a `BloomFilter` of them is needed
+/// (the ART compiler's word list, and the query the searcher copies into
+/// one), so they are hashable as-is without needing a wrapper type.
+impl Hashable for Vec<u8> {
+    fn bytes(&self) -> &[u8] {
+        self
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::{BloomFilter, Hashable};
@@ -0,0 +1,161 @@
+//! Byte equivalence classes over the wide children arrays.
+//!
+//! On a real dictionary the alphabet actually used is tiny (ASCII words use
+//! well under a hundred distinct bytes), yet a node reserves one slot per
+//! possible byte (256). A `ByteClasses` table maps every byte that is
+//! actually used as an edge to its own small class id (`0..alphabet_len`) and
+//! collapses all the never-used bytes to a single sentinel class.
+//!
+//! `MiniSearch::load` builds the table by scanning every edge of the loaded
+//! trie, and its distance search then walks only the representative byte of
+//! each used class. The table is not stored in any file, and nodes on disk
+//! still reserve all 256 children slots.
+
+/// The sentinel class shared by every byte that is never used as an edge.
+/// It is always `0` so that a zeroed table maps everything to "unused".
+pub const UNUSED_CLASS: u8 = 0;
+
+/// A mapping from the 256 possible bytes to a small set of class ids.
+/// Two bytes share a class only if neither is ever used as an edge.
+#[derive(Debug, Clone)]
+pub struct ByteClasses {
+    /// For each byte, the class it belongs to.
+    classes: [u8; 256],
+    /// For each class, one representative byte, so that a class id stored in a
+    /// node edge can be translated back to the character it stands for.
+    /// The unused sentinel class has no representative (`0`).
+    representatives: [u8; 256],
+    /// How many distinct classes exist, including the unused sentinel.
+    alphabet_len: usize,
+}
+
+impl ByteClasses {
+    /// Create a table where every byte maps to the unused sentinel class.
+    /// A table covering actual edges is built with `from_used` instead.
+    pub fn new() -> Self {
+        ByteClasses {
+            classes: [UNUSED_CLASS; 256],
+            representatives: [0; 256],
+            alphabet_len: 1, // Only the unused class for now.
+        }
+    }
+
+    /// Build the identity table where each byte is its own class.
+    /// Used as a transparent default when no compression is wanted, so that
+    /// translating a byte through it is a no-op.
+    pub fn identity() -> Self {
+        let mut classes = [0; 256];
+        for (byte, class) in classes.iter_mut().enumerate() {
+            *class = byte as u8;
+        }
+
+        ByteClasses {
+            classes,
+            representatives: classes,
+            alphabet_len: 256,
+        }
+    }
+
+    /// Build a table from the set of bytes that are actually used as edges.
+    /// Each distinct used byte is assigned its own class in ascending order,
+    /// and every other byte keeps the shared unused sentinel class.
+    pub fn from_used<I: IntoIterator<Item = u8>>(used: I) -> Self {
+        let mut present = [false; 256];
+        for byte in used {
+            present[byte as usize] = true;
+        }
+
+        let mut classes = [UNUSED_CLASS; 256];
+        let mut representatives = [0; 256];
+        let mut next_class: u8 = 1; // 0 is the unused sentinel.
+        for (byte, used) in present.iter().enumerate() {
+            if *used {
+                classes[byte] = next_class;
+                representatives[next_class as usize] = byte as u8;
+                next_class += 1;
+            }
+        }
+
+        ByteClasses {
+            classes,
+            representatives,
+            alphabet_len: next_class as usize,
+        }
+    }
+
+    /// Get the class id of the given byte.
+    pub fn get(&self, byte: u8) -> u8 {
+        self.classes[byte as usize]
+    }
+
+    /// Get the representative byte of the given class id, i.e. the character
+    /// that an edge stored with this class stands for. This is the inverse of
+    /// `get` for every used byte.
+    pub fn byte(&self, class: u8) -> u8 {
+        self.representatives[class as usize]
+    }
+
+    /// How many distinct classes exist, which is the width a node needs for
+    /// its children array (the unused sentinel included).
+    pub fn alphabet_len(&self) -> usize {
+        self.alphabet_len
+    }
+
+    /// Get the raw 256 entries table, which `from_bytes` turns back into the
+    /// same table.
+    pub fn bytes(&self) -> &[u8; 256] {
+        &self.classes
+    }
+
+    /// Rebuild a table from its 256 raw entries, as returned by `bytes`.
+    pub fn from_bytes(bytes: &[u8; 256]) -> Self {
+        let mut representatives = [0; 256];
+        for (byte, class) in bytes.iter().enumerate() {
+            if *class != UNUSED_CLASS {
+                representatives[*class as usize] = byte as u8;
+            }
+        }
+
+        ByteClasses {
+            classes: *bytes,
+            representatives,
+            alphabet_len: bytes.iter().map(|class| *class as usize).max().unwrap_or(0) + 1,
+        }
+    }
+}
+
+impl Default for ByteClasses {
+    fn default() -> Self {
+        ByteClasses::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ByteClasses, UNUSED_CLASS};
+
+    #[test]
+    fn compacts_used_bytes() {
+        let classes = ByteClasses::from_used([b'a', b'c', b'a'].iter().copied());
+
+        // Two distinct used bytes plus the unused sentinel.
+        assert_eq!(3, classes.alphabet_len());
+        // Used bytes get their own ascending class.
+        assert_eq!(1, classes.get(b'a'));
+        assert_eq!(2, classes.get(b'c'));
+        // Never used bytes collapse to the sentinel.
+        assert_eq!(UNUSED_CLASS, classes.get(b'b'));
+        assert_eq!(UNUSED_CLASS, classes.get(b'z'));
+    }
+
+    #[test]
+    fn round_trip_through_bytes() {
+        let classes = ByteClasses::from_used([b't', b'e', b's'].iter().copied());
+        let restored = ByteClasses::from_bytes(classes.bytes());
+
+        assert_eq!(classes.alphabet_len(), restored.alphabet_len());
+        for byte in 0..=255u8 {
+            assert_eq!(classes.get(byte), restored.get(byte));
+        }
+    }
+}
@@ -0,0 +1,65 @@
+//! A counting global allocator, modeled on Meilisearch's `CountingAlloc`,
+//! installed for tests so a structure's self-reported logical size (e.g.
+//! `ArtSearch::memory_usage`) can be compared with the peak of heap bytes
+//! allocated while it is built and queried. Only heap allocations made
+//! through it are counted, not the whole resident set.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static RESIDENT: AtomicUsize = AtomicUsize::new(0);
+static MAX_RESIDENT: AtomicUsize = AtomicUsize::new(0);
+
+/// Wraps the system allocator, tracking bytes currently allocated through it
+/// and the high-water mark ever reached.
+pub struct CountingAlloc;
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let resident = RESIDENT.fetch_add(layout.size(), Ordering::SeqCst) + layout.size();
+        MAX_RESIDENT.fetch_max(resident, Ordering::SeqCst);
+
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        RESIDENT.fetch_sub(layout.size(), Ordering::SeqCst);
+        System.dealloc(ptr, layout)
+    }
+}
+
+/// Bytes currently allocated through `CountingAlloc`.
+pub fn resident() -> usize {
+    RESIDENT.load(Ordering::SeqCst)
+}
+
+/// The largest `resident()` has been since the last `reset_max_resident`.
+pub fn max_resident() -> usize {
+    MAX_RESIDENT.load(Ordering::SeqCst)
+}
+
+/// Reset the high-water mark to the current resident size, so a test can
+/// measure the peak of just the section of code that follows.
+pub fn reset_max_resident() {
+    MAX_RESIDENT.store(RESIDENT.load(Ordering::SeqCst), Ordering::SeqCst);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tracks_allocation_and_deallocation() {
+        reset_max_resident();
+        let before = resident();
+
+        let data = vec![0u8; 4096];
+
+        assert!(resident() >= before + 4096);
+        assert!(max_resident() >= before + 4096);
+
+        drop(data);
+
+        assert_eq!(resident(), before);
+    }
+}
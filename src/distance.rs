@@ -37,6 +37,95 @@ pub trait IncrementalDistance: core::fmt::Debug {
     /// Does calling push more times will keep the distance
     /// under the given threshold
     fn can_continue(&self, max_distance: usize) -> bool;
+
+    /// A lower bound on `distance()` after any number of further `push`
+    /// calls: admissible, so it never overestimates what is still
+    /// reachable, which makes it safe to use as a best-first search
+    /// priority. `can_continue(max_distance)` is exactly
+    /// `self.lower_bound() <= max_distance`.
+    fn lower_bound(&self) -> usize;
+
+    /// Clone this automaton into a freshly boxed, independent copy.
+    /// Used by best-first searches that need to fork a separate automaton
+    /// per branch of the trie instead of sharing one and backtracking with
+    /// `push`/`pop`. A plain `Clone` supertrait would make the trait object
+    /// unusable (`Clone::clone` isn't object-safe), so this is the
+    /// object-safe way to get the same effect.
+    fn box_clone(&self) -> Box<dyn IncrementalDistance>;
+
+    /// Switch between matching the whole word (the default) and prefix
+    /// mode, where `distance` instead reports the best edit distance
+    /// between the whole word and any prefix of the word built up so far
+    /// by `push` -- i.e. the word being searched is allowed to be a
+    /// completion of the current trie path rather than equal to it.
+    ///
+    /// Like `reset`, this should be called on a "clean" (just created or
+    /// reset) instance; switching mode mid-walk is not supported.
+    fn set_prefix_mode(&mut self, is_prefix: bool);
+}
+
+/// Per-operation edit costs for a weighted Damerau-Levenshtein distance.
+/// Each of the four edit operations carries its own independent cost so that
+/// domain-specific typo profiles can be modelled (e.g. OCR confusions or
+/// keyboard-adjacency substitutions cheaper than arbitrary ones).
+#[derive(Debug, Clone, Copy)]
+pub struct Weights {
+    /// Cost of inserting a character.
+    pub insert: usize,
+    /// Cost of deleting a character.
+    pub delete: usize,
+    /// Cost of substituting one character for another.
+    pub substitute: usize,
+    /// Cost of transposing two adjacent characters.
+    pub transpose: usize,
+}
+
+impl Weights {
+    /// The unit weights, reproducing the plain Damerau-Levenshtein distance.
+    pub fn unit() -> Self {
+        Weights {
+            insert: 1,
+            delete: 1,
+            substitute: 1,
+            transpose: 1,
+        }
+    }
+
+    /// The smallest strictly positive operation cost, used to keep the
+    /// early-stop pruning sound: a branch must only be cut when not even the
+    /// cheapest possible operation could bring it back under the threshold.
+    fn min_positive(&self) -> usize {
+        [self.insert, self.delete, self.substitute, self.transpose]
+            .iter()
+            .copied()
+            .filter(|weight| *weight > 0)
+            .min()
+            .unwrap_or(1)
+    }
+}
+
+impl Default for Weights {
+    fn default() -> Self {
+        Weights::unit()
+    }
+}
+
+/// A single operation of the edit script that turns the current word into the
+/// matched word, as recovered by `DamerauLevenshteinDistance::operations`.
+/// Each variant carries the indices of the characters it involves in the word
+/// (`word_index`) and in the current word (`current_index`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EditOp {
+    /// The two characters are equal, no edit is needed.
+    Match { word_index: usize, current_index: usize },
+    /// The current character is replaced by the word one.
+    Substitute { word_index: usize, current_index: usize },
+    /// A current character is inserted to reach the word.
+    Insert { current_index: usize },
+    /// A word character is deleted to reach the word.
+    Delete { word_index: usize },
+    /// Two adjacent characters are swapped.
+    Transpose { word_index: usize, current_index: usize },
 }
 
 /// Calculate the distance between a word and all words present in a trie.
@@ -52,6 +141,8 @@ pub trait IncrementalDistance: core::fmt::Debug {
 pub struct DamerauLevenshteinDistance {
     /// The word that need to be matched against all the other one.
     word: Vec<u8>,
+    /// The per-operation edit costs. Unit weights give the classic distance.
+    weights: Weights,
     /// All the characters that have been previously added and not popped.
     /// They are needed for the transposition part of the algorithm.
     current: Vec<u8>,
@@ -64,6 +155,14 @@ pub struct DamerauLevenshteinDistance {
     /// For each rows, was it the minimum in it ?
     /// Used for early stopping to prevent going to far.
     min_distances: Vec<usize>,
+    /// Whether `distance` reports a whole-word match (the default) or a
+    /// prefix match (see `IncrementalDistance::set_prefix_mode`).
+    is_prefix: bool,
+    /// In prefix mode, the running minimum of the row-ending cell (word vs.
+    /// the current prefix built so far) over every prefix length reached
+    /// since the last `reset`. Kept as a stack, one entry per pushed byte
+    /// plus the initial (empty prefix) one, so `pop` can restore it.
+    prefix_distances: Vec<usize>,
 }
 
 impl DamerauLevenshteinDistance {
@@ -79,19 +178,118 @@ impl DamerauLevenshteinDistance {
     /// Doing so allows to pre-reserve the capacity of the distance matrix
     /// so that no other resize is needed.
     pub fn new_with_words_len(word: &[u8], max_words_len: usize) -> Self {
+        DamerauLevenshteinDistance::new_inner(word, max_words_len, Weights::unit())
+    }
+
+    /// Create a new distance calculator using the given per-operation weights.
+    /// With non-unit weights the bit-vector calculator can no longer serve the
+    /// request, so callers must stick to this dynamic-programming version.
+    pub fn new_weighted(word: &[u8], weights: Weights) -> Self {
+        DamerauLevenshteinDistance::new_inner(word, word.len(), weights)
+    }
+
+    fn new_inner(word: &[u8], max_words_len: usize, weights: Weights) -> Self {
         let mut matrix = Vec::with_capacity((word.len() + 1) * (max_words_len + 1));
-        (0..=word.len()).for_each(|value| matrix.push(value));
+        // The first row matches an empty current against longer and longer
+        // word prefixes, accumulating one delete weight per character.
+        (0..=word.len()).for_each(|value| matrix.push(value * weights.delete));
 
         let mut min_distances = Vec::with_capacity(max_words_len + 1);
         min_distances.push(0); //The minimum distances in the first line is 0.
 
+        let mut prefix_distances = Vec::with_capacity(max_words_len + 1);
+        // Row 0 (empty current) ends at `word.len() * weights.delete`.
+        prefix_distances.push(word.len() * weights.delete);
+
         DamerauLevenshteinDistance {
             word: word.into(),
+            weights,
             current: Vec::with_capacity(max_words_len),
             distances: matrix,
             min_distances,
+            is_prefix: false,
+            prefix_distances,
         }
     }
+
+    /// Recover the sequence of edit operations realizing the distance computed
+    /// by the last `push`, by backtracing the full matrix from cell
+    /// `[current.len()][word.len()]` down to `[0][0]`.
+    ///
+    /// This is only available on this dynamic-programming calculator; the
+    /// bit-vector one discards the intermediate cells and so cannot recover the
+    /// alignment.
+    pub fn operations(&self) -> Vec<EditOp> {
+        let width = self.word.len() + 1;
+        let cell = |i: usize, j: usize| self.distances[i * width + j];
+
+        let mut operations = Vec::new();
+        let mut i = self.current.len();
+        let mut j = self.word.len();
+
+        while i > 0 || j > 0 {
+            let current = cell(i, j);
+
+            if i > 0 && j > 0 {
+                let cost = (self.word[j - 1] != self.current[i - 1]) as usize;
+
+                // Transposition jump to [i-2][j-2] when it was the minimizer.
+                if i >= 2
+                    && j >= 2
+                    && self.word[j - 2] == self.current[i - 1]
+                    && self.word[j - 1] == self.current[i - 2]
+                    && current == cell(i - 2, j - 2) + self.weights.transpose
+                {
+                    operations.push(EditOp::Transpose {
+                        word_index: j - 2,
+                        current_index: i - 2,
+                    });
+                    i -= 2;
+                    j -= 2;
+                    continue;
+                }
+
+                // Diagonal step for a match or a substitution.
+                if current == cell(i - 1, j - 1) + cost * self.weights.substitute {
+                    operations.push(if cost == 0 {
+                        EditOp::Match {
+                            word_index: j - 1,
+                            current_index: i - 1,
+                        }
+                    } else {
+                        EditOp::Substitute {
+                            word_index: j - 1,
+                            current_index: i - 1,
+                        }
+                    });
+                    i -= 1;
+                    j -= 1;
+                    continue;
+                }
+            }
+
+            // Left step: a word character is deleted.
+            if j > 0 && current == cell(i, j - 1) + self.weights.delete {
+                operations.push(EditOp::Delete { word_index: j - 1 });
+                j -= 1;
+                continue;
+            }
+
+            // Up step: a current character is inserted.
+            if i > 0 && current == cell(i - 1, j) + self.weights.insert {
+                operations.push(EditOp::Insert { current_index: i - 1 });
+                i -= 1;
+                continue;
+            }
+
+            // No predecessor matched, which should not happen on a consistent
+            // matrix; stop to stay safe rather than loop forever.
+            break;
+        }
+
+        operations.reverse();
+        operations
+    }
 }
 
 impl IncrementalDistance for DamerauLevenshteinDistance {
@@ -116,15 +314,19 @@ impl IncrementalDistance for DamerauLevenshteinDistance {
         }
 
         unsafe {
-            let mut min_distance = self.current.len();
+            // The first column matches an empty word against the current,
+            // accumulating one insert weight per character already pushed.
+            let mut min_distance = self.current.len() * self.weights.insert;
             *self.distances.get_unchecked_mut(offset) = min_distance;
 
             for index in 1..matrix_width {
                 let cost = (*self.word.get_unchecked(index - 1) != value) as usize;
 
-                let deletion = self.distances.get_unchecked(offset + index - 1) + 1;
-                let insertion = self.distances.get_unchecked(previous_offset + index) + 1;
-                let substitution = self.distances.get_unchecked(previous_offset + index - 1) + cost;
+                let deletion = self.distances.get_unchecked(offset + index - 1) + self.weights.delete;
+                let insertion =
+                    self.distances.get_unchecked(previous_offset + index) + self.weights.insert;
+                let substitution =
+                    self.distances.get_unchecked(previous_offset + index - 1) + cost * self.weights.substitute;
                 let transposition = if index >= 2
                     && self.current.len() >= 2
                     && *self.word.get_unchecked(index - 2) == value
@@ -133,7 +335,7 @@ impl IncrementalDistance for DamerauLevenshteinDistance {
                 {
                     self.distances
                         .get_unchecked(previous_previous_offset + index - 2)
-                        + cost
+                        + self.weights.transpose
                 } else {
                     // Create a big enought value so that only 3 min are needed
                     // instead of 4. Reduce computation needed.
@@ -151,11 +353,17 @@ impl IncrementalDistance for DamerauLevenshteinDistance {
             *self.min_distances.get_unchecked_mut(self.current.len()) = min_distance;
 
             // Get the calculated distances of the new words.
-            *self.distances.get_unchecked(offset + matrix_width - 1)
+            let row_end = *self.distances.get_unchecked(offset + matrix_width - 1);
+
+            let previous_prefix_distance = *self.prefix_distances.get_unchecked(self.prefix_distances.len() - 1);
+            self.prefix_distances.push(min(previous_prefix_distance, row_end));
+
+            row_end
         }
     }
 
     fn pop(&mut self) -> bool {
+        self.prefix_distances.pop();
         self.current.pop().is_some()
     }
 
@@ -163,13 +371,16 @@ impl IncrementalDistance for DamerauLevenshteinDistance {
         // Clear all buffers
         self.distances.clear();
         self.min_distances.clear();
+        self.prefix_distances.clear();
         self.current.clear();
         self.word.clear();
 
-        // Reset the distance matrix
-        (0..=word.len()).for_each(|value| self.distances.push(value));
+        // Reset the distance matrix, accumulating delete weights as in new.
+        (0..=word.len()).for_each(|value| self.distances.push(value * self.weights.delete));
         // Reset the min_distance matrix
         self.min_distances.push(0);
+        // Row 0 (empty current) ends at `word.len() * weights.delete`.
+        self.prefix_distances.push(word.len() * self.weights.delete);
 
         // Set the new wanted word
         self.word.extend_from_slice(word);
@@ -184,17 +395,42 @@ impl IncrementalDistance for DamerauLevenshteinDistance {
     }
 
     fn distance(&self) -> usize {
+        if self.is_prefix {
+            return *self.prefix_distances.last().unwrap();
+        }
+
         self.distances[self.current.len().saturating_add(1) * self.word.len().saturating_add(1) - 1]
     }
 
-    fn can_continue(&self, max_distance: usize) -> bool {
+    fn lower_bound(&self) -> usize {
         let width = self.word.len().saturating_add(1);
         let distance_offset = self.current.len().saturating_add(1) * width - 1;
 
-        *unsafe { self.min_distances.get_unchecked(self.current.len()) } <= max_distance
-            || (self.current.len() >= 2
-                && self.word.len() >= 2
-                && self.distances[distance_offset - 2 * width - 2] < max_distance)
+        let mut bound = *unsafe { self.min_distances.get_unchecked(self.current.len()) };
+
+        if self.is_prefix {
+            bound = bound.min(*self.prefix_distances.last().unwrap());
+        }
+
+        if self.current.len() >= 2 && self.word.len() >= 2 {
+            bound = bound.min(
+                self.distances[distance_offset - 2 * width - 2] + self.weights.min_positive(),
+            );
+        }
+
+        bound
+    }
+
+    fn can_continue(&self, max_distance: usize) -> bool {
+        self.lower_bound() <= max_distance
+    }
+
+    fn box_clone(&self) -> Box<dyn IncrementalDistance> {
+        Box::new(self.clone())
+    }
+
+    fn set_prefix_mode(&mut self, is_prefix: bool) {
+        self.is_prefix = is_prefix;
     }
 }
 
@@ -242,12 +478,36 @@ pub struct DamerauLevenshteinBitDistance {
     /// after the previous one have been calculated
     /// HP: HPj[i] = 1 if D[i,j]-D[i,j-1] = 1
     /// HN: HNj[i] = 1 if D[i,j]-D[i,j-1] = -1
-    /// Each bit-vector takes only ONE DamerauLevenshteinBitType,
-    /// so it can't be used on too big distances or words.
+    /// To lift the single machine word length ceiling, each bit-vector now
+    /// spans `nb_blocks` consecutive `DamerauLevenshteinBitType` of `w` bits.
+    /// The blocks of a vector are stored least-significant first, so block 0
+    /// holds bits `0..w`, block 1 holds bits `w..2w`, and so on.
     bit_vectors: Vec<DamerauLevenshteinBitType>,
+    /// How many blocks each bit-vector spans, so that a word of any length
+    /// can be handled (a single block being the common, fastest case).
+    nb_blocks: usize,
     /// For each rows, the minimun and the current distance (in this order).
     /// Used for early stopping to prevent going to far.
     distances: Vec<BitDistance>,
+    /// Whether `distance` reports a whole-word match (the default) or a
+    /// prefix match (see `IncrementalDistance::set_prefix_mode`).
+    is_prefix: bool,
+    /// In prefix mode, the running minimum of `distances[i].distance` over
+    /// every prefix length reached since the last `reset`. Kept as a stack,
+    /// one entry per pushed byte plus the initial (empty prefix) one, so
+    /// `pop` can restore it.
+    prefix_distances: Vec<usize>,
+}
+
+/// How many bits are stored in a single bit-vector block.
+const BLOCK_BITS: usize = size_of::<DamerauLevenshteinBitType>() * 8;
+
+/// How many blocks are needed to hold a bit-vector covering the given word.
+/// One extra bit over the word length is enough room for the horizontal
+/// shifts: `word_len + 1` bits rounded up to whole blocks, which is
+/// `word_len / BLOCK_BITS + 1`.
+fn blocks_for(word_len: usize) -> usize {
+    word_len / BLOCK_BITS + 1
 }
 
 impl DamerauLevenshteinBitDistance {
@@ -263,10 +523,15 @@ impl DamerauLevenshteinBitDistance {
     /// Doing so allows to pre-reserve the capacity of the distance matrix
     /// so that no other resize is needed.
     pub fn new_with_words_len(word: &[u8], max_words_len: usize) -> Self {
-        let mut bit_vectors = Vec::with_capacity(NB_BIT_VECTORS * (max_words_len + 1));
-        // Fill the first bit_vectors with zero for initialisation
-        bit_vectors.resize(NB_BIT_VECTORS, 0);
-        bit_vectors[2/*VP*/] = !0;
+        let nb_blocks = blocks_for(word.len());
+
+        let mut bit_vectors = Vec::with_capacity(NB_BIT_VECTORS * nb_blocks * (max_words_len + 1));
+        // Fill the first bit_vectors with zero for initialisation, then set
+        // every VP block to all-ones as required by the algorithm.
+        bit_vectors.resize(NB_BIT_VECTORS * nb_blocks, 0);
+        for block in 0..nb_blocks {
+            bit_vectors[block * NB_BIT_VECTORS + 2/*VP*/] = !0;
+        }
 
         let mut distances = Vec::with_capacity(max_words_len + 1);
         distances.push(BitDistance {
@@ -278,15 +543,21 @@ impl DamerauLevenshteinBitDistance {
             word: word.into(),
             current: Vec::with_capacity(max_words_len),
             bit_vectors,
+            nb_blocks,
             distances,
+            is_prefix: false,
+            prefix_distances: vec![word.len()],
         }
     }
 
-    pub fn allows(&self, word: &[u8], max_distance: usize) -> bool {
-        word.len() + max_distance
-                   + 1// To detect out of max distance word
-                   + 2// For transposition bound.
-            <= size_of::<DamerauLevenshteinBitType>() * 8
+    /// The chunked bit-vector spans as many blocks as needed, so any word
+    /// length is supported. The single-block case stays the fast common path.
+    ///
+    /// The bit-vector recurrence is hard-wired to unit operation costs, so a
+    /// weighted request (`DamerauLevenshteinDistance::new_weighted`) cannot be
+    /// served here and callers must fall back to the DP calculator for it.
+    pub fn allows(&self, _word: &[u8], _max_distance: usize) -> bool {
+        true
     }
 }
 
@@ -294,7 +565,7 @@ impl IncrementalDistance for DamerauLevenshteinBitDistance {
     fn push(&mut self, value: u8) -> usize {
         self.current.push(value);
 
-        debug_assert!(self.allows(self.current(), 0));
+        let stride = NB_BIT_VECTORS * self.nb_blocks;
 
         if self.distances.len() <= self.current.len() {
             // min_distance grows at the same times as the bit_vectors matrix
@@ -304,7 +575,7 @@ impl IncrementalDistance for DamerauLevenshteinBitDistance {
             // Resizing the bit_vectors matrix if needed so that the new element
             // can be correctly inserted without any problem.
             self.bit_vectors
-                .resize(NB_BIT_VECTORS * (self.current.len() + 1), 0);
+                .resize(stride * (self.current.len() + 1), 0);
         }
 
         // PM: PMc[i] = 1 if A[i] = c
@@ -314,68 +585,104 @@ impl IncrementalDistance for DamerauLevenshteinBitDistance {
         // VP: VPj[i] = 1 if D[i,j]-D[i-1,j] = 1
         // VN: VNj[i] = 1 if D[i,j]-D[i-1,j] = -1
 
-        let offset = self.current.len() * NB_BIT_VECTORS;
-
-        // compute PM
-        let mut pm = 0;
-        for index in 0..self.word.len() {
-            if value == *unsafe { self.word.get_unchecked(index) } {
-                pm |= 1 << index;
+        let offset = self.current.len() * stride;
+        let previous_offset = offset - stride;
+
+        // The word bit at index `word.len() - 1` carries the final distance.
+        let last_bit = self.word.len() - 1;
+        let last_block = last_bit / BLOCK_BITS;
+        let last_block_mask = 1_usize << (last_bit % BLOCK_BITS);
+
+        let previous_distance =
+            unsafe { self.distances.get_unchecked(self.current.len() - 1).distance };
+        let mut new_distance = previous_distance;
+
+        // Horizontal carries propagated block by block (least significant
+        // first): `carry_add` for the D0 integer addition, `carry_hp`/`carry_hn`
+        // for the `<< 1` horizontal shifts, `carry_trans` for the transposition
+        // term shift.
+        let mut carry_add: DamerauLevenshteinBitType = 0;
+        let mut carry_hp: DamerauLevenshteinBitType = 0;
+        let mut carry_hn: DamerauLevenshteinBitType = 0;
+        let mut carry_trans: DamerauLevenshteinBitType = 0;
+
+        for block in 0..self.nb_blocks {
+            // compute PM for the bits of this block
+            let base = block * BLOCK_BITS;
+            let mut pm: DamerauLevenshteinBitType = 0;
+            for bit in 0..BLOCK_BITS {
+                let index = base + bit;
+                if index < self.word.len() && value == *unsafe { self.word.get_unchecked(index) } {
+                    pm |= 1 << bit;
+                }
             }
-        }
-        let pm = pm;
-
-        // Get all previous bit_vectors
-        let previous_offset = offset - NB_BIT_VECTORS;
-        let (pm_1, d0_1, vp_1, vn_1) = unsafe {
-            (
-                *self.bit_vectors.get_unchecked(previous_offset),
-                *self.bit_vectors.get_unchecked(previous_offset + 1),
-                *self.bit_vectors.get_unchecked(previous_offset + 2),
-                *self.bit_vectors.get_unchecked(previous_offset + 3),
-            )
-        };
 
-        // Compute the new bit_vectors
-        let d0 = ((!d0_1) & pm).overflowing_shl(1).0 & pm_1;
-        let d0 = d0 | (((pm & vp_1).overflowing_add(vp_1).0) ^ vp_1) | pm | vn_1;
-        let hp = vn_1 | !(d0 | vp_1);
-        let hn = d0 & vp_1;
-
-        let hp_shiffted = hp.overflowing_shl(1).0;
-        let hn_shiffted = hn.overflowing_shl(1).0;
+            // Get the previous row bit_vectors for this block.
+            let block_offset = previous_offset + block * NB_BIT_VECTORS;
+            let (pm_1, d0_1, vp_1, vn_1) = unsafe {
+                (
+                    *self.bit_vectors.get_unchecked(block_offset),
+                    *self.bit_vectors.get_unchecked(block_offset + 1),
+                    *self.bit_vectors.get_unchecked(block_offset + 2),
+                    *self.bit_vectors.get_unchecked(block_offset + 3),
+                )
+            };
 
-        let vp = hn_shiffted | !(d0 | (hp_shiffted | 1));
-        let vn = d0 & (hp_shiffted | 1);
+            // Transposition term, shifting the top bit of the block into the
+            // bottom bit of the next one.
+            let trans_src = (!d0_1) & pm;
+            let trans = ((trans_src << 1) | carry_trans) & pm_1;
+            carry_trans = trans_src >> (BLOCK_BITS - 1);
+
+            // Standard D0 addition, propagating the carry out of this block.
+            let (sum, carry_1) = (pm & vp_1).overflowing_add(vp_1);
+            let (sum, carry_2) = sum.overflowing_add(carry_add);
+            carry_add = (carry_1 | carry_2) as DamerauLevenshteinBitType;
+
+            let d0 = trans | (sum ^ vp_1) | pm | vn_1;
+            let hp = vn_1 | !(d0 | vp_1);
+            let hn = d0 & vp_1;
+
+            // The horizontal delta at the virtual column -1 is +1, hence the
+            // extra bottom bit injected into the first block only.
+            let hp_low = if block == 0 { 1 } else { carry_hp };
+            let hn_low = if block == 0 { 0 } else { carry_hn };
+            let hp_shiffted = (hp << 1) | hp_low;
+            let hn_shiffted = (hn << 1) | hn_low;
+            carry_hp = hp >> (BLOCK_BITS - 1);
+            carry_hn = hn >> (BLOCK_BITS - 1);
+
+            let vp = hn_shiffted | !(d0 | hp_shiffted);
+            let vn = d0 & hp_shiffted;
+
+            // Insert all values back into the matrix.
+            let block_offset = offset + block * NB_BIT_VECTORS;
+            unsafe {
+                *self.bit_vectors.get_unchecked_mut(block_offset) = pm;
+                *self.bit_vectors.get_unchecked_mut(block_offset + 1) = d0;
+                *self.bit_vectors.get_unchecked_mut(block_offset + 2) = vp;
+                *self.bit_vectors.get_unchecked_mut(block_offset + 3) = vn;
+            }
 
-        // Insert all values back into the iterator
-        unsafe {
-            *self.bit_vectors.get_unchecked_mut(offset) = pm;
-            *self.bit_vectors.get_unchecked_mut(offset + 1) = d0;
-            *self.bit_vectors.get_unchecked_mut(offset + 2) = vp;
-            *self.bit_vectors.get_unchecked_mut(offset + 3) = vn;
+            // The final distance lives at the highest set bit of the last block.
+            if block == last_block {
+                new_distance = previous_distance + ((hp & last_block_mask) != 0) as usize
+                    - ((hn & last_block_mask) != 0) as usize;
+            }
         }
 
-        // Construct the new distance, min distance and min distance index
-        let previous_info = unsafe { self.distances.get_unchecked(self.current.len() - 1) };
-
-        let word_len_mask = 1_usize.overflowing_shl(self.word.len() as u32 - 1).0;
-        let new_distance = previous_info.distance + ((hp & word_len_mask) != 0) as usize
-            - ((hn & word_len_mask) != 0) as usize;
-
-        // Get the new min_distance by searching it in the row.
+        // Get the new min_distance by walking the row from the last bit down.
         let mut new_min_distance = new_distance;
-        let mut new_min_distance_mask = word_len_mask;
-
-        while new_min_distance_mask != 0 {
-            let tmp_new_min_distance = new_min_distance
-                - ((vp & new_min_distance_mask) != 0) as usize
-                + ((vn & new_min_distance_mask) != 0) as usize;
-
-            new_min_distance_mask = new_min_distance_mask.overflowing_shr(1).0;
-
-            if tmp_new_min_distance < new_min_distance {
-                new_min_distance = tmp_new_min_distance;
+        let mut running = new_distance;
+        for index in (0..self.word.len()).rev() {
+            let block_offset = offset + (index / BLOCK_BITS) * NB_BIT_VECTORS;
+            let mask = 1_usize << (index % BLOCK_BITS);
+            let vp = unsafe { *self.bit_vectors.get_unchecked(block_offset + 2) };
+            let vn = unsafe { *self.bit_vectors.get_unchecked(block_offset + 3) };
+
+            running = running - ((vp & mask) != 0) as usize + ((vn & mask) != 0) as usize;
+            if running < new_min_distance {
+                new_min_distance = running;
             }
         }
 
@@ -386,17 +693,28 @@ impl IncrementalDistance for DamerauLevenshteinBitDistance {
             };
         }
 
+        let previous_prefix_distance = *self.prefix_distances.last().unwrap();
+        self.prefix_distances.push(min(previous_prefix_distance, new_distance));
+
         new_distance
     }
 
     fn pop(&mut self) -> bool {
+        self.prefix_distances.pop();
         self.current.pop().is_some()
     }
 
     fn reset(&mut self, word: &[u8]) {
-        // Keep the firsts bit_vectors for initialisation
-        self.bit_vectors.resize(NB_BIT_VECTORS, 0);
-        debug_assert!(self.bit_vectors[2/*VP*/] != 0);
+        // The new word may need a different number of blocks.
+        self.nb_blocks = blocks_for(word.len());
+
+        // Keep the firsts bit_vectors for initialisation, with every VP block
+        // reset to all-ones as the algorithm requires.
+        self.bit_vectors.clear();
+        self.bit_vectors.resize(NB_BIT_VECTORS * self.nb_blocks, 0);
+        for block in 0..self.nb_blocks {
+            self.bit_vectors[block * NB_BIT_VECTORS + 2/*VP*/] = !0;
+        }
 
         // Clear all buffers
         self.distances.resize_with(1, Default::default); // Keep the first distance already inserted.
@@ -405,6 +723,9 @@ impl IncrementalDistance for DamerauLevenshteinBitDistance {
         self.current.clear();
         self.word.clear();
 
+        self.prefix_distances.clear();
+        self.prefix_distances.push(word.len());
+
         // Set the new wanted word
         self.word.extend_from_slice(word);
     }
@@ -418,26 +739,326 @@ impl IncrementalDistance for DamerauLevenshteinBitDistance {
     }
 
     fn distance(&self) -> usize {
+        if self.is_prefix {
+            return *self.prefix_distances.last().unwrap();
+        }
+
         self.distances[self.current.len()].distance
     }
 
+    fn lower_bound(&self) -> usize {
+        let mut bound = self.distances[self.current.len()].min_distance;
+
+        if self.is_prefix {
+            bound = bound.min(*self.prefix_distances.last().unwrap());
+        }
+
+        if self.current.len() >= 2 && self.word.len() >= 2 {
+            // Bound on the replacement distance.
+            // The two relevant bits (word.len()-2 and word.len()-1) may
+            // straddle a block boundary, so they are read one by one.
+            let offset = (self.current.len() - 2) * NB_BIT_VECTORS * self.nb_blocks;
+
+            let mut vp_count = 0usize;
+            let mut vn_count = 0usize;
+            for index in [self.word.len() - 2, self.word.len() - 1] {
+                let block_offset = offset + (index / BLOCK_BITS) * NB_BIT_VECTORS;
+                let mask = 1_usize << (index % BLOCK_BITS);
+                vp_count += ((self.bit_vectors[block_offset + 2/* VP */] & mask) != 0) as usize;
+                vn_count += ((self.bit_vectors[block_offset + 3/* VN */] & mask) != 0) as usize;
+            }
+
+            let replacement_distance = self.distances[self.current.len() - 2].distance
+                // Addition and substraction are inverted as we are going backward
+                - vp_count
+                + vn_count;
+
+            // The row only proves `replacement_distance < max_distance`, i.e.
+            // a bound of `replacement_distance + 1`, not `replacement_distance` itself.
+            bound = bound.min(replacement_distance + 1);
+        }
+
+        bound
+    }
+
     fn can_continue(&self, max_distance: usize) -> bool {
-        // There is still a possibility of inferior distance in the row
-        self.distances[self.current.len()].min_distance <= max_distance
-            || (self.current.len() >= 2
-                && self.word.len() >= 2
-                && {
-                    // Test for replacement distance < max_distance.
-                    let offset = self.current.len() - 2;
-                    let mask = 3_usize.overflowing_shl(self.word.len() as u32 - 2).0;
-
-                    (self.distances[offset].distance
-                    // Addition and substraction are inverted as we are going backward
-                    - (mask & self.bit_vectors[offset * NB_BIT_VECTORS + 2/* VP */]).count_ones() as usize
-                        + (mask & self.bit_vectors[offset * NB_BIT_VECTORS + 3/* VN */])
-                            .count_ones() as usize)
-                        < max_distance
-                })
+        self.lower_bound() <= max_distance
+    }
+
+    fn box_clone(&self) -> Box<dyn IncrementalDistance> {
+        Box::new(self.clone())
+    }
+
+    fn set_prefix_mode(&mut self, is_prefix: bool) {
+        self.is_prefix = is_prefix;
+    }
+}
+
+/// A single state of the universal Levenshtein (here, Damerau-Levenshtein)
+/// automaton: `i` query characters consumed, `e` errors spent to get there.
+type AutomatonState = (usize, usize);
+
+/// Close a set of states under the deletion edge: `(i, e)` can reach
+/// `(i + 1, e + 1)` for free, without consuming an input byte, by skipping a
+/// query character. This has to be applied transitively (a run of several
+/// skipped characters costs one error each) until no more states can be
+/// added, which takes at most `word_len` rounds since `i` only grows.
+fn close_deletions(mut states: Vec<AutomatonState>, word_len: usize, max_errors: usize) -> Vec<AutomatonState> {
+    let mut frontier = states.clone();
+
+    while !frontier.is_empty() {
+        let mut next_frontier = Vec::new();
+
+        for (i, e) in frontier {
+            if i < word_len && e + 1 <= max_errors {
+                next_frontier.push((i + 1, e + 1));
+            }
+        }
+
+        states.extend_from_slice(&next_frontier);
+        frontier = next_frontier;
+    }
+
+    states
+}
+
+/// Keep only the Pareto-minimal states: drop any `(i, e)` subsumed by another
+/// `(i', e')` of the same set, i.e. one that is at least as far along and no
+/// more costly (`i' >= i`, `e' <= e`) *and* can still close the gap it left
+/// behind within its error savings (`i' - i <= e - e'`, so the skipped query
+/// characters can be paid for with deletions out of the errors it saved).
+/// That last condition matters: without it a state can look dominated while
+/// actually being cheaper to complete, since catching up costs one error per
+/// skipped character.
+fn prune(mut states: Vec<AutomatonState>) -> Vec<AutomatonState> {
+    states.sort_unstable();
+    states.dedup();
+
+    let snapshot = states.clone();
+    states.retain(|&(i, e)| {
+        !snapshot.iter().any(|&(other_i, other_e)| {
+            (other_i, other_e) != (i, e)
+                && other_i >= i
+                && other_e <= e
+                && other_i - i <= e - other_e
+        })
+    });
+    states
+}
+
+/// Calculate the distance between a word and all words present in a trie,
+/// driving the walk with a precomputed universal Levenshtein automaton
+/// instead of recomputing a DP column on every `push`, like
+/// `DamerauLevenshteinDistance` does.
+///
+/// The active set only ever holds the Pareto-minimal `(i, e)` states (`i`
+/// query characters consumed, `e` errors spent), so each `push` touches
+/// `O(k)` states instead of `O(word.len())` matrix cells, and an empty
+/// active set is an immediate signal that the current branch can be pruned.
+///
+/// The number of errors `k` this automaton can ever report is fixed at
+/// construction, since it bounds how many `(i, e)` states are kept around;
+/// like the unit weights baked into `DamerauLevenshteinBitDistance`, this is
+/// the tradeoff made in exchange for the faster `push`.
+#[derive(Debug, Clone)]
+pub struct LevenshteinAutomatonDistance {
+    /// The word that need to be matched against all the other one.
+    word: Vec<u8>,
+    /// All the characters that have been previously added and not popped.
+    current: Vec<u8>,
+    /// The largest amount of errors a state of the active set can carry.
+    max_errors: usize,
+    /// The active state set after each pushed byte, kept as a stack so
+    /// `pop` can restore the previous set in O(1). `states[0]` is the set
+    /// before any byte has been pushed.
+    states: Vec<Vec<AutomatonState>>,
+    /// The transposition edge spans two pushed bytes, so it can't be
+    /// resolved by looking only at the active set: `pending[k]` holds the
+    /// `(i, e)` states, active *before* push `k`, for which push `k`'s byte
+    /// matched `word[i + 1]` -- the first half of a swap. If the *next*
+    /// pushed byte matches `word[i]`, the swap completes to `(i + 2, e + 1)`.
+    /// Kept as a stack alongside `states` so `pop` can restore it; `pending[0]`
+    /// is empty since there is no byte before the first push to pair with.
+    pending: Vec<Vec<AutomatonState>>,
+    /// Whether `distance` reports a whole-word match (the default) or a
+    /// prefix match (see `IncrementalDistance::set_prefix_mode`).
+    is_prefix: bool,
+    /// In prefix mode, the running minimum of the whole-word distance over
+    /// every prefix length reached since the last `reset`. Kept as a
+    /// stack, one entry per pushed byte plus the initial (empty prefix)
+    /// one, so `pop` can restore it.
+    prefix_distances: Vec<usize>,
+}
+
+impl LevenshteinAutomatonDistance {
+    /// Create a new automaton for the given word, able to report distances
+    /// up to `max_errors` (inclusive).
+    pub fn new(word: &[u8], max_errors: usize) -> Self {
+        let initial = prune(close_deletions(vec![(0, 0)], word.len(), max_errors));
+
+        LevenshteinAutomatonDistance {
+            word: word.into(),
+            current: Vec::new(),
+            max_errors,
+            states: vec![initial],
+            pending: vec![Vec::new()],
+            is_prefix: false,
+            prefix_distances: vec![word.len()],
+        }
+    }
+
+    /// The whole-word distance for a given active state set: the smallest
+    /// `e` among the states that have consumed the whole query, or
+    /// `max_errors + 1` (out of range) if none has.
+    fn whole_word_distance(states: &[AutomatonState], word_len: usize, max_errors: usize) -> usize {
+        states
+            .iter()
+            .filter(|&&(i, _)| i == word_len)
+            .map(|&(_, e)| e)
+            .min()
+            .unwrap_or(max_errors + 1)
+    }
+
+    /// Compute the active set reached after reading `value` from the
+    /// current active set, following every match/substitution, insertion,
+    /// deletion and transposition edge, bounded by `max_errors`; also
+    /// returns the transposition markers `value` itself arms for the byte
+    /// that comes after it (see `pending`).
+    fn advance(&self, value: u8) -> (Vec<AutomatonState>, Vec<AutomatonState>) {
+        let active = self.states.last().unwrap();
+        let mut next = Vec::new();
+
+        for &(i, e) in active {
+            // Insertion: `value` is an extra character of the word being
+            // walked, not present in the query.
+            if e + 1 <= self.max_errors {
+                next.push((i, e + 1));
+            }
+
+            if i < self.word.len() {
+                // Match / substitution.
+                let substitution_errors = if value == self.word[i] { e } else { e + 1 };
+                if substitution_errors <= self.max_errors {
+                    next.push((i + 1, substitution_errors));
+                }
+            }
+        }
+
+        // Transposition completion: the byte pushed just before `value`
+        // armed these states by matching `word[i + 1]`; if `value` matches
+        // `word[i]` too, the pair completes the swap.
+        for &(i, e) in self.pending.last().unwrap() {
+            if e + 1 <= self.max_errors && value == self.word[i] {
+                next.push((i + 2, e + 1));
+            }
+        }
+
+        // Transposition arming: record which of the states active *before*
+        // `value` are the first half of a potential swap, to be resolved by
+        // whatever byte comes next.
+        let mut pending = Vec::new();
+        for &(i, e) in active {
+            if i + 1 < self.word.len() && e + 1 <= self.max_errors && value == self.word[i + 1] {
+                pending.push((i, e));
+            }
+        }
+
+        // Deletion is an epsilon move (it skips a query character without
+        // consuming `value`), so it is not one of the per-byte edges above;
+        // it is folded in afterwards as a closure over whatever the
+        // byte-consuming edges just reached.
+        (prune(close_deletions(next, self.word.len(), self.max_errors)), pending)
+    }
+}
+
+impl IncrementalDistance for LevenshteinAutomatonDistance {
+    fn push(&mut self, value: u8) -> usize {
+        let (next, pending) = self.advance(value);
+        self.current.push(value);
+
+        let whole_word = Self::whole_word_distance(&next, self.word.len(), self.max_errors);
+        let previous_prefix_distance = *self.prefix_distances.last().unwrap();
+        self.prefix_distances.push(min(previous_prefix_distance, whole_word));
+
+        self.states.push(next);
+        self.pending.push(pending);
+
+        self.distance()
+    }
+
+    fn pop(&mut self) -> bool {
+        if self.current.pop().is_some() {
+            self.states.pop();
+            self.pending.pop();
+            self.prefix_distances.pop();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn reset(&mut self, word: &[u8]) {
+        self.word.clear();
+        self.word.extend_from_slice(word);
+
+        self.current.clear();
+        self.states.clear();
+        self.states.push(prune(close_deletions(vec![(0, 0)], word.len(), self.max_errors)));
+        self.pending.clear();
+        self.pending.push(Vec::new());
+
+        self.prefix_distances.clear();
+        self.prefix_distances.push(word.len());
+    }
+
+    fn word(&self) -> &[u8] {
+        &self.word
+    }
+
+    fn current(&self) -> &[u8] {
+        self.current.as_slice()
+    }
+
+    fn distance(&self) -> usize {
+        if self.is_prefix {
+            return *self.prefix_distances.last().unwrap();
+        }
+
+        Self::whole_word_distance(self.states.last().unwrap(), self.word.len(), self.max_errors)
+    }
+
+    fn lower_bound(&self) -> usize {
+        // `e` only ever grows as more bytes are pushed, so the smallest `e`
+        // among the active states is a true lower bound on the final
+        // distance. An empty active set means no state can ever match
+        // again, which we represent with a bound past every possible
+        // `max_errors`.
+        let mut bound = self
+            .states
+            .last()
+            .unwrap()
+            .iter()
+            .map(|&(_, e)| e)
+            .min()
+            .unwrap_or(self.max_errors + 1);
+
+        if self.is_prefix {
+            bound = bound.min(*self.prefix_distances.last().unwrap());
+        }
+
+        bound
+    }
+
+    fn can_continue(&self, max_distance: usize) -> bool {
+        self.lower_bound() <= max_distance
+    }
+
+    fn box_clone(&self) -> Box<dyn IncrementalDistance> {
+        Box::new(self.clone())
+    }
+
+    fn set_prefix_mode(&mut self, is_prefix: bool) {
+        self.is_prefix = is_prefix;
     }
 }
 
@@ -445,7 +1066,7 @@ impl IncrementalDistance for DamerauLevenshteinBitDistance {
 mod tests {
     use super::{
         DamerauLevenshteinBitDistance, DamerauLevenshteinDistance, IncrementalDistance,
-        NB_BIT_VECTORS,
+        LevenshteinAutomatonDistance, NB_BIT_VECTORS,
     };
 
     #[test]
@@ -512,6 +1133,90 @@ mod tests {
         }
     }
 
+    #[test]
+    fn operations() {
+        use super::EditOp;
+
+        // A single transposition between the two words.
+        let mut distance_calculator = DamerauLevenshteinDistance::new("ab".as_bytes());
+        "ba".as_bytes()
+            .iter()
+            .for_each(|value| {
+                distance_calculator.push(*value);
+            });
+        assert_eq!(
+            vec![EditOp::Transpose {
+                word_index: 0,
+                current_index: 0
+            }],
+            distance_calculator.operations()
+        );
+
+        // Identical words yield only matches.
+        let mut distance_calculator = DamerauLevenshteinDistance::new("abc".as_bytes());
+        "abc".as_bytes()
+            .iter()
+            .for_each(|value| {
+                distance_calculator.push(*value);
+            });
+        assert_eq!(
+            vec![
+                EditOp::Match {
+                    word_index: 0,
+                    current_index: 0
+                },
+                EditOp::Match {
+                    word_index: 1,
+                    current_index: 1
+                },
+                EditOp::Match {
+                    word_index: 2,
+                    current_index: 2
+                },
+            ],
+            distance_calculator.operations()
+        );
+    }
+
+    #[test]
+    fn weighted_distance() {
+        use super::Weights;
+
+        // A costly transposition makes two substitutions the cheaper path.
+        let weights = Weights {
+            insert: 1,
+            delete: 1,
+            substitute: 1,
+            transpose: 5,
+        };
+        let mut distance_calculator =
+            DamerauLevenshteinDistance::new_weighted("ab".as_bytes(), weights);
+        let calculated_distance = "ba"
+            .as_bytes()
+            .iter()
+            .map(|value| distance_calculator.push(*value))
+            .last()
+            .unwrap();
+        assert_eq!(2, calculated_distance);
+
+        // A cheap transposition is preferred over two substitutions.
+        let weights = Weights {
+            insert: 2,
+            delete: 2,
+            substitute: 2,
+            transpose: 1,
+        };
+        let mut distance_calculator =
+            DamerauLevenshteinDistance::new_weighted("ab".as_bytes(), weights);
+        let calculated_distance = "ba"
+            .as_bytes()
+            .iter()
+            .map(|value| distance_calculator.push(*value))
+            .last()
+            .unwrap();
+        assert_eq!(1, calculated_distance);
+    }
+
     #[test]
     fn reset() {
         let first_word = "hello";
@@ -579,6 +1284,18 @@ mod tests {
             ("kynar", "kayna", 2),
             ("muahahah", "muhahahah", 1),
             ("sakit", "safekit", 2),
+            // Words longer than a single machine word, to exercise the
+            // multi-block bit-vector path.
+            (
+                "aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa",
+                "aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa",
+                0,
+            ),
+            (
+                "aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa",
+                "aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaabaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa",
+                1,
+            ),
         ]
         .iter()
         {
@@ -639,4 +1356,88 @@ mod tests {
         // The matching word have been changed meanwhile
         assert_ne!(0, calculated_distance);
     }
+
+    #[test]
+    fn automaton_distance() {
+        for (word_1, word_2, distance) in [
+            ("kitten", "sitting", 3),
+            ("Saturday", "Sunday", 3),
+            ("Something", "Smoething", 1),
+            ("Pomatomus", "Pomatomus", 0),
+            ("kynar", "kaynar", 1),
+            ("kynar", "kayna", 2),
+            ("ab", "ba", 1),
+        ]
+        .iter()
+        {
+            let mut distance_calculator = LevenshteinAutomatonDistance::new(word_1.as_bytes(), 10);
+            let calculated_distance = word_2
+                .as_bytes()
+                .iter()
+                .map(|value| distance_calculator.push(*value))
+                .last()
+                .unwrap_or(word_1.len());
+
+            assert_eq!(
+                *distance, calculated_distance,
+                "Distance between {} and {} is wrong. Got {}, expected {} ({:?})",
+                word_1, word_2, calculated_distance, distance, distance_calculator
+            );
+
+            assert_eq!(
+                *distance,
+                distance_calculator.distance(),
+                "Distance between {} and {} is wrong. Got {}, expected {} ({:?})",
+                word_1,
+                word_2,
+                distance_calculator.distance(),
+                distance,
+                distance_calculator
+            );
+        }
+    }
+
+    #[test]
+    fn automaton_prunes_when_over_budget() {
+        // "kitten" -> "sitting" needs 3 edits, so a 2-error budget must be
+        // exhausted partway through and never report an accepting state.
+        let mut distance_calculator = LevenshteinAutomatonDistance::new("kitten".as_bytes(), 2);
+
+        for value in "sitting".as_bytes() {
+            distance_calculator.push(*value);
+        }
+
+        assert!(distance_calculator.distance() > 2);
+        assert!(!distance_calculator.can_continue(2));
+    }
+
+    #[test]
+    fn automaton_reset() {
+        let first_word = "hello";
+        let second_word = "world";
+
+        let mut distance_calculator = LevenshteinAutomatonDistance::new(first_word.as_bytes(), 10);
+        let calculated_distance = first_word
+            .as_bytes()
+            .iter()
+            .map(|value| distance_calculator.push(*value))
+            .last()
+            .unwrap();
+
+        // This is the same word
+        assert_eq!(0, calculated_distance);
+
+        // Reseting the distance calculator
+        distance_calculator.reset(second_word.as_bytes());
+
+        let calculated_distance = first_word
+            .as_bytes()
+            .iter()
+            .map(|value| distance_calculator.push(*value))
+            .last()
+            .unwrap();
+
+        // The matching word have been changed meanwhile
+        assert_ne!(0, calculated_distance);
+    }
 }
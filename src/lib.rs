@@ -1,11 +1,24 @@
+pub mod bloom;
+pub mod byte_classes;
+#[cfg(test)]
+pub mod counting_alloc;
 pub mod dictionary;
 pub mod distance;
+pub mod jaro_winkler;
 pub mod limit;
+pub mod lru_cache;
 pub mod trie;
 pub mod art;
 
 mod memory;
 
+/// Tracks peak heap allocation during tests (see `counting_alloc`) so it can be
+/// checked against a structure's self-reported logical size, e.g.
+/// `art::ArtSearch::memory_usage`.
+#[cfg(test)]
+#[global_allocator]
+static GLOBAL: counting_alloc::CountingAlloc = counting_alloc::CountingAlloc;
+
 use core::num::NonZeroU32;
 use core::cmp::Ordering;
 
@@ -23,7 +36,7 @@ pub type WordFrequency = NonZeroU32;
 /// The basic structure that need to be used for each search structure.
 /// Each struture must be capable of storing the associated
 /// data with it so that it can be retrieve without any problem
-#[derive(PartialEq, Eq)]
+#[derive(PartialEq, Eq, Clone)]
 pub struct WordData {
     /// A slice of the word.
     /// Doesn't directly store the word
@@ -90,6 +103,36 @@ pub trait Search {
     /// -  300 queries/seconds with a 1 distance.
     /// -   30 queries/seconds with a 2 distance.
     fn search(&self, distance: &mut IncrementalDistance, max_distance: usize) -> Box<dyn Iterator<Item=WordData>>;
+
+    /// Autocomplete: return the `k` most frequent words starting with
+    /// `prefix`, ordered by frequency descending. Unlike `search`, this is
+    /// an exact prefix match rather than a fuzzy one, so every returned
+    /// `WordData` has `distance == 0`.
+    ///
+    /// A prefix that diverges from every word in the structure (including
+    /// one ending partway through a compressed path, for structures that
+    /// have one) must return an empty iterator rather than panicking.
+    fn search_prefix(&self, prefix: &[u8], k: usize) -> Box<dyn Iterator<Item=WordData>>;
+}
+
+/// A search structure whose compiled file embeds enough redundancy to check
+/// its own node bytes for corruption, or to confirm two copies hold the
+/// identical dictionary without diffing them byte for byte.
+pub trait Verifiable : Search {
+    /// Recompute every node's hash from the bytes currently on disk and
+    /// compare them against the tree embedded at compile time, returning
+    /// the indices (in on-disk node order) whose hash no longer matches.
+    ///
+    /// Returns `Ok(())` both when every node hashed clean and when the file
+    /// holds no such tree at all (e.g. compiled without opting into this
+    /// feature, or reopened and edited since): there is nothing to check
+    /// either way, so use `root_hash` first to tell the two cases apart.
+    fn verify(&self) -> Result<(), Vec<usize>>;
+
+    /// The root hash recorded at compile time, or `None` if this file holds
+    /// no such tree. Two dictionaries with the same root are guaranteed to
+    /// hold byte-identical node bytes.
+    fn root_hash(&self) -> Option<[u8; 32]>;
 }
 
 /// Get information about a search structure
@@ -0,0 +1,113 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// A fixed-capacity cache that evicts the least recently used entry once
+/// full. Recency is tracked with a side list of keys from least to most
+/// recently used; this trades an O(n) `touch` for a much simpler
+/// implementation than an intrusive linked list, which is fine for the
+/// small capacities (tens to low hundreds of entries) this is meant to be
+/// sized at, e.g. for the spellcheck server's per-query result cache.
+pub struct LruCache<K, V> {
+    capacity: usize,
+    map: HashMap<K, V>,
+    /// Keys ordered from least to most recently used.
+    order: Vec<K>,
+}
+
+impl<K: Eq + Hash + Clone, V> LruCache<K, V> {
+    /// Create a cache that holds at most `capacity` entries.
+    pub fn new(capacity: usize) -> Self {
+        assert!(capacity > 0, "LRU cache capacity must be positive");
+
+        LruCache {
+            capacity,
+            map: HashMap::with_capacity(capacity),
+            order: Vec::with_capacity(capacity),
+        }
+    }
+
+    /// Look up `key`, marking it as the most recently used entry on a hit.
+    pub fn get(&mut self, key: &K) -> Option<&V> {
+        if self.map.contains_key(key) {
+            self.touch(key);
+        }
+
+        self.map.get(key)
+    }
+
+    /// Insert or update `key`, marking it as the most recently used entry.
+    /// Evicts the least recently used entry first if the cache is full.
+    pub fn put(&mut self, key: K, value: V) {
+        if self.map.contains_key(&key) {
+            self.touch(&key);
+            self.map.insert(key, value);
+            return;
+        }
+
+        if self.map.len() >= self.capacity {
+            let lru_key = self.order.remove(0);
+            self.map.remove(&lru_key);
+        }
+
+        self.order.push(key.clone());
+        self.map.insert(key, value);
+    }
+
+    /// How many entries are currently held.
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    fn touch(&mut self, key: &K) {
+        if let Some(position) = self.order.iter().position(|candidate| candidate == key) {
+            let key = self.order.remove(position);
+            self.order.push(key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LruCache;
+
+    #[test]
+    fn hit_and_miss() {
+        let mut cache = LruCache::new(2);
+
+        assert_eq!(cache.get(&"a"), None);
+
+        cache.put("a", 1);
+        assert_eq!(cache.get(&"a"), Some(&1));
+    }
+
+    #[test]
+    fn evicts_least_recently_used() {
+        let mut cache = LruCache::new(2);
+
+        cache.put("a", 1);
+        cache.put("b", 2);
+        // Touching "a" makes "b" the least recently used entry.
+        cache.get(&"a");
+
+        cache.put("c", 3);
+
+        assert_eq!(cache.get(&"b"), None);
+        assert_eq!(cache.get(&"a"), Some(&1));
+        assert_eq!(cache.get(&"c"), Some(&3));
+    }
+
+    #[test]
+    fn updating_a_key_keeps_it_most_recently_used() {
+        let mut cache = LruCache::new(2);
+
+        cache.put("a", 1);
+        cache.put("b", 2);
+        cache.put("a", 10);
+
+        cache.put("c", 3);
+
+        // "b" was the least recently used entry, not "a".
+        assert_eq!(cache.get(&"b"), None);
+        assert_eq!(cache.get(&"a"), Some(&10));
+    }
+}
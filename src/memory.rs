@@ -1,5 +1,5 @@
 use std::os::unix::io::AsRawFd;
-use std::fs::File;
+use std::fs::{File, OpenOptions};
 use std::io::{Seek, SeekFrom};
 
 // All C function that need to be called.
@@ -76,4 +76,276 @@ impl Drop for DiskMemory {
             }
         }
     }
+}
+
+/// Below this unreachable-bytes ratio, `maybe_compact` appends and tolerates
+/// the waste rather than paying for an `O(n)` rewrite on every small edit.
+pub const ACCEPTABLE_UNREACHABLE_BYTES_RATIO: f32 = 0.3;
+
+/// The small fixed header `GrowableDiskMemory` keeps at the start of its
+/// file, so the unreachable-bytes count survives a close and reopen.
+#[repr(C)]
+struct GrowableHeader {
+    /// Bytes within the file that used to hold a node but no longer do,
+    /// e.g. the old slot left behind by a `Node256` -> `Node4` downgrade.
+    unreachable_bytes: usize,
+}
+
+const GROWABLE_HEADER_SIZE: usize = core::mem::size_of::<GrowableHeader>();
+
+/// A writable counterpart to `DiskMemory`.
+/// The file is mapped read-write and new data is always allocated from the
+/// end: a node is never overwritten or reused in place, so a reader that
+/// already resolved an offset keeps seeing the same bytes.
+/// Replacing or shrinking a node (e.g. through the `Node256` -> `NodeN`
+/// `From` impls) leaves its old slot as dead space; the caller reports it
+/// through `mark_unreachable` so `maybe_compact` knows when the waste has
+/// grown large enough to be worth a full rewrite.
+#[derive(Debug)]
+pub struct GrowableDiskMemory {
+    /// The file where the data is stored.
+    file: File,
+    /// The mmap-ed file, mapped read-write.
+    data: *mut u8,
+    /// The length of the mapped file, header included.
+    length: usize,
+}
+
+impl GrowableDiskMemory {
+    /// Create a new, empty growable memory, truncating any existing file.
+    pub fn create(filename: &str) -> Result<Self, String> {
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(filename)
+            .map_err(|error| format!("Can't create new memory for \"{}\" ({})", filename, error))?;
+
+        file
+            .set_len(GROWABLE_HEADER_SIZE as u64)
+            .map_err(|error| format!("Can't reserve the header for \"{}\" ({})", filename, error))?;
+
+        let mut memory = GrowableDiskMemory {
+            file,
+            data: std::mem::align_of::<u8>() as *mut u8,
+            length: 0,
+        };
+
+        memory.remap(GROWABLE_HEADER_SIZE)?;
+        memory.header_mut().unreachable_bytes = 0;
+
+        Ok(memory)
+    }
+
+    /// Open an already-created growable memory file so new nodes can be
+    /// appended to it. A caller that wants the "on open" half of the
+    /// compaction heuristic should follow up with `maybe_compact` right
+    /// after opening.
+    pub fn open(filename: &str) -> Result<Self, String> {
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(filename)
+            .map_err(|error| format!("Can't open memory \"{}\" ({})", filename, error))?;
+
+        let len = file
+            .seek(SeekFrom::End(0))
+            .map_err(|error| format!("Can't tell file size {}", error))? as usize;
+
+        if len < GROWABLE_HEADER_SIZE {
+            return Err(format!("File \"{}\" is too small to contain a growable memory header", filename));
+        }
+
+        let mut memory = GrowableDiskMemory {
+            file,
+            data: std::mem::align_of::<u8>() as *mut u8,
+            length: 0,
+        };
+
+        memory.remap(len)?;
+
+        Ok(memory)
+    }
+
+    /// Drop the current mapping (if any) and map the first `length` bytes of
+    /// the file read-write, growing the file first if it is currently
+    /// smaller than that.
+    fn remap(&mut self, length: usize) -> Result<(), String> {
+        if self.length != 0 {
+            unsafe {
+                munmap(self.data as *mut i8, self.length);
+            }
+        }
+
+        self.file
+            .set_len(length as u64)
+            .map_err(|error| format!("Can't resize memory file: {}", error))?;
+
+        let fd = self.file.as_raw_fd();
+        let ptr = unsafe {
+            mmap(std::ptr::null_mut(), length, 0x1 | 0x2 /* Read | Write */, 0x0001 /* MAP_SHARED */, fd, 0) as *mut u8
+        };
+
+        if ptr == !0 as *mut u8 {
+            return Err(String::from("Could not mmap, need file with same rights as those requested"));
+        }
+
+        self.data = ptr;
+        self.length = length;
+
+        Ok(())
+    }
+
+    fn header(&self) -> &GrowableHeader {
+        unsafe { &*(self.data as *const GrowableHeader) }
+    }
+
+    fn header_mut(&mut self) -> &mut GrowableHeader {
+        unsafe { &mut *(self.data as *mut GrowableHeader) }
+    }
+
+    /// The number of content bytes mapped, header excluded.
+    pub fn len(&self) -> usize {
+        self.length - GROWABLE_HEADER_SIZE
+    }
+
+    /// Bytes that used to hold a node but can no longer be reached.
+    pub fn unreachable_bytes(&self) -> usize {
+        self.header().unreachable_bytes
+    }
+
+    pub fn data(&self) -> *const u8 {
+        unsafe { self.data.add(GROWABLE_HEADER_SIZE) }
+    }
+
+    pub fn data_mut(&mut self) -> *mut u8 {
+        unsafe { self.data.add(GROWABLE_HEADER_SIZE) }
+    }
+
+    /// Grow the file by `size` bytes and return the offset (relative to the
+    /// end of the header, like every other offset in this format) the
+    /// caller can write its new node at. Never reuses space freed by
+    /// `mark_unreachable`.
+    pub fn allocate(&mut self, size: usize) -> Result<usize, String> {
+        let offset = self.len();
+        self.remap(self.length + size)?;
+        Ok(offset)
+    }
+
+    /// Record that `size` bytes, previously allocated but now superseded
+    /// (e.g. a downgraded node's old slot), are no longer reachable.
+    pub fn mark_unreachable(&mut self, size: usize) {
+        self.header_mut().unreachable_bytes += size;
+    }
+
+    /// Reset the unreachable-bytes counter, typically right after a caller
+    /// has rewritten the live data into a fresh, dense file.
+    pub fn reset_unreachable(&mut self) {
+        self.header_mut().unreachable_bytes = 0;
+    }
+
+    /// If the unreachable-bytes ratio exceeds `ratio`, hand the live content
+    /// to `rewrite` so it can be re-emitted densely, then swap it in for
+    /// `self`. Below the threshold this is a no-op and `self` keeps
+    /// appending, tolerating the waste. Returns whether a compaction ran.
+    pub fn maybe_compact<F>(&mut self, ratio: f32, rewrite: F) -> Result<bool, String>
+    where
+        F: FnOnce(&GrowableDiskMemory) -> Result<Self, String>,
+    {
+        let total = self.len();
+
+        if total == 0 || (self.unreachable_bytes() as f32 / total as f32) <= ratio {
+            return Ok(false);
+        }
+
+        *self = rewrite(self)?;
+
+        Ok(true)
+    }
+}
+
+impl Drop for GrowableDiskMemory {
+    fn drop(&mut self) {
+        if self.length != 0 {
+            unsafe {
+                munmap(self.data as *mut i8, self.length);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_bytes(memory: &mut GrowableDiskMemory, offset: usize, bytes: &[u8]) {
+        unsafe {
+            std::ptr::copy_nonoverlapping(bytes.as_ptr(), memory.data_mut().add(offset), bytes.len());
+        }
+    }
+
+    fn read_bytes(memory: &GrowableDiskMemory, offset: usize, len: usize) -> Vec<u8> {
+        unsafe { std::slice::from_raw_parts(memory.data().add(offset), len).to_vec() }
+    }
+
+    #[test]
+    fn allocate_and_reopen_round_trips_data() {
+        let path = format!("/tmp/growable_memory_test_{}_a.bin", std::process::id());
+        let mut memory = GrowableDiskMemory::create(&path).unwrap();
+
+        let offset = memory.allocate(4).unwrap();
+        write_bytes(&mut memory, offset, b"abcd");
+        drop(memory);
+
+        let reopened = GrowableDiskMemory::open(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(reopened.len(), 4);
+        assert_eq!(read_bytes(&reopened, offset, 4), b"abcd");
+        assert_eq!(reopened.unreachable_bytes(), 0);
+    }
+
+    /// Mirrors the shape of an `ArtCompiler::update`/`remove` sequence:
+    /// superseded bytes pile up as `unreachable_bytes` while `self` keeps
+    /// appending, until the ratio crosses the threshold and a rewrite
+    /// (re-emitting only the still-live bytes) is triggered.
+    #[test]
+    fn maybe_compact_waits_for_the_threshold_then_rewrites_only_the_live_bytes() {
+        let path = format!("/tmp/growable_memory_test_{}_b.bin", std::process::id());
+        let compacted_path = format!("{}.compact", path);
+        let mut memory = GrowableDiskMemory::create(&path).unwrap();
+
+        let live = memory.allocate(66).unwrap();
+        write_bytes(&mut memory, live, &[1u8; 66]);
+        memory.allocate(34).unwrap();
+
+        // 32 / 100 == 0.32, below the 0.33 threshold: no compaction yet.
+        memory.mark_unreachable(32);
+        let ran = memory
+            .maybe_compact(0.33, |_| unreachable!("must not compact below the threshold"))
+            .unwrap();
+        assert!(!ran);
+
+        // 34 / 100 == 0.34, over the threshold: triggers a rewrite that
+        // only carries the still-live 66 bytes forward.
+        memory.mark_unreachable(2);
+        let ran = memory
+            .maybe_compact(0.33, |old| {
+                let mut fresh = GrowableDiskMemory::create(&compacted_path)?;
+                let new_offset = fresh.allocate(66)?;
+                write_bytes(&mut fresh, new_offset, &read_bytes(old, live, 66));
+                Ok(fresh)
+            })
+            .unwrap();
+        assert!(ran);
+
+        assert_eq!(memory.len(), 66);
+        assert_eq!(memory.unreachable_bytes(), 0);
+        assert_eq!(read_bytes(&memory, 0, 66), vec![1u8; 66]);
+
+        drop(memory);
+        std::fs::remove_file(&path).unwrap();
+        std::fs::remove_file(&compacted_path).unwrap();
+    }
 }
\ No newline at end of file
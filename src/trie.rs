@@ -5,8 +5,10 @@
 //! don't perform worse than this structure.
 
 use core::num::NonZeroUsize;
+use std::collections::HashMap;
 
 use crate::{Compiler, Search, Information, WordData, WordFrequency};
+use crate::byte_classes::ByteClasses;
 use crate::distance::IncrementalDistance;
 use crate::memory::{Memory, MemoryAccess};
 
@@ -72,14 +74,224 @@ impl Compiler for MiniCompiler {
     }
 }
 
+/// The signature of a DAWG state: its data and its (byte, child) edges.
+/// Two states are equivalent (and thus mergeable) if and only if they have
+/// the same signature, which is exactly what turns the trie into a minimal
+/// acyclic automaton.
+type DawgKey = (Option<WordFrequency>, Vec<(u8, usize)>);
+
+/// A state kept in RAM while the DAWG is being built.
+#[derive(Clone)]
+struct DawgNode {
+    /// The associated frequency of the word, if the state ends a word.
+    frequency: Option<WordFrequency>,
+    /// The outgoing edges, kept sorted by byte (words are added in order,
+    /// so a new edge is always greater than the existing ones).
+    children: Vec<(u8, usize)>,
+}
+
+/// Build the minimal acyclic automaton (a DAWG) of a sorted dictionary
+/// incrementally, deduplicating the shared suffixes as words are added.
+///
+/// `bin/compiler.rs` already sorts the dictionary before compiling, which is exactly
+/// the precondition Daciuk's algorithm needs: as each word is added, the
+/// divergent suffix of the previous word is minimized bottom-up against a
+/// register of canonical states. A state whose signature is already in the
+/// register is discarded and its parent re-points to the canonical one,
+/// turning the trie into a DAG with far fewer nodes (and thus a much smaller
+/// memory footprint against the `Limit::Memory` budget).
+///
+/// The output uses the exact same `MiniNode` layout as `MiniCompiler`, so
+/// `MiniSearch` loads and queries a DAWG without any change.
+pub struct MiniDawgCompiler {
+    /// Where the minimized nodes are written once `build` is called.
+    memory: Memory<MiniNode>,
+    /// The node pool. Node 0 is always the root, and is never registered as
+    /// it has no parent to re-point.
+    nodes: Vec<DawgNode>,
+    /// The canonical states already registered, keyed by their signature.
+    register: HashMap<DawgKey, usize>,
+    /// The previously added word, to know which suffix must be minimized.
+    previous_word: Vec<u8>,
+}
+
+impl MiniDawgCompiler {
+    pub fn new(filename: &str) -> Self {
+        let memory = Memory::new(filename, MemoryAccess::ReadWrite).expect("Can't create file based memory");
+
+        MiniDawgCompiler {
+            memory,
+            nodes: vec![DawgNode {
+                frequency: None,
+                children: Vec::new(),
+            }],
+            register: HashMap::new(),
+            previous_word: Vec::new(),
+        }
+    }
+
+    /// The length of the common prefix between the word and the previously
+    /// added one, which is the part of the active path that is reused.
+    fn common_prefix_len(&self, word: &[u8]) -> usize {
+        word.iter()
+            .zip(self.previous_word.iter())
+            .take_while(|(a, b)| a == b)
+            .count()
+    }
+
+    /// Follow the existing (unregistered) path spelled by the prefix and
+    /// return the state reached.
+    fn traverse(&self, prefix: &[u8]) -> usize {
+        let mut node = 0;
+        for value in prefix {
+            node = self.nodes[node]
+                .children
+                .iter()
+                .find(|(byte, _)| byte == value)
+                .expect("The common prefix is always an existing path")
+                .1;
+        }
+
+        node
+    }
+
+    /// Add the suffix as a fresh chain of states below the given state,
+    /// marking the last one as the end of the word.
+    fn add_suffix(&mut self, mut node: usize, suffix: &[u8], frequency: WordFrequency) {
+        for value in suffix {
+            let child = self.nodes.len();
+            self.nodes.push(DawgNode {
+                frequency: None,
+                children: Vec::new(),
+            });
+
+            self.nodes[node].children.push((*value, child));
+            node = child;
+        }
+
+        self.nodes[node].frequency = Some(frequency);
+    }
+
+    /// Minimize the last child chain of the given state bottom-up: a child
+    /// equivalent to an already registered state is replaced by it, otherwise
+    /// it becomes canonical and is added to the register.
+    fn replace_or_register(&mut self, state: usize) {
+        let last = self.nodes[state].children.len() - 1;
+        let child = self.nodes[state].children[last].1;
+
+        if !self.nodes[child].children.is_empty() {
+            self.replace_or_register(child);
+        }
+
+        let key = (self.nodes[child].frequency, self.nodes[child].children.clone());
+        if let Some(&canonical) = self.register.get(&key) {
+            // An equivalent state already exists, re-point the parent to it
+            // and let the duplicate become unreachable.
+            self.nodes[state].children[last].1 = canonical;
+        } else {
+            self.register.insert(key, child);
+        }
+    }
+
+    /// Write the live (reachable) part of the DAWG to the file in the
+    /// `MiniNode` layout, sharing a node between all the parents that point
+    /// to it.
+    fn write_to_file(&mut self) {
+        // Assign a file index to each reachable state in discovery order,
+        // the root getting index 0 as `MiniSearch` expects.
+        let mut index_of: HashMap<usize, usize> = HashMap::new();
+        let mut order: Vec<usize> = vec![0];
+        index_of.insert(0, 0);
+
+        let mut cursor = 0;
+        while cursor < order.len() {
+            let node = order[cursor];
+            let children = self.nodes[node].children.clone();
+            for (_, child) in children {
+                if !index_of.contains_key(&child) {
+                    index_of.insert(child, order.len());
+                    order.push(child);
+                }
+            }
+            cursor += 1;
+        }
+
+        for node in order {
+            let mut children = [None; 256];
+            for (value, child) in self.nodes[node].children.iter() {
+                children[*value as usize] = NonZeroUsize::new(index_of[child]);
+            }
+
+            self.memory
+                .push(MiniNode {
+                    frequency: self.nodes[node].frequency,
+                    children,
+                })
+                .unwrap();
+        }
+    }
+}
+
+impl Compiler for MiniDawgCompiler {
+    fn add(&mut self, word: &[u8], frequency: WordFrequency) {
+        let common_prefix = self.common_prefix_len(word);
+        let last_state = self.traverse(&word[..common_prefix]);
+
+        // Minimize the divergent suffix of the previously added word.
+        if !self.nodes[last_state].children.is_empty() {
+            self.replace_or_register(last_state);
+        }
+
+        self.add_suffix(last_state, &word[common_prefix..], frequency);
+        self.previous_word = word.to_vec();
+    }
+
+    fn build(mut self) {
+        // Minimize the suffix of the very last word, then flush to the file.
+        if !self.nodes[0].children.is_empty() {
+            self.replace_or_register(0);
+        }
+
+        self.write_to_file();
+    }
+}
+
 pub struct MiniSearch {
-    memory: Memory<MiniNode>
+    memory: Memory<MiniNode>,
+    /// The bytes actually used as edges, in ascending order.
+    /// A node still reserves the full 256 children slots on disk, but the
+    /// distance search only ever visits this live alphabet (derived from the
+    /// byte equivalence classes), which is far fewer than 256 on a real
+    /// (mostly ASCII) dictionary and cuts the scan cost accordingly.
+    live_bytes: Vec<u8>,
 }
 
 impl MiniSearch {
     pub fn load(filename: &str) -> Result<Self, String> {
+        let memory = Memory::open(filename, MemoryAccess::ReadOnly)?;
+
+        // Scan every node once to discover which bytes are actually used as
+        // edges, and derive the equivalence classes from them.
+        let mut used = Vec::new();
+        for node_index in 0..memory.len() {
+            for (byte, child) in memory[node_index].children.iter().enumerate() {
+                if child.is_some() {
+                    used.push(byte as u8);
+                }
+            }
+        }
+
+        let classes = ByteClasses::from_used(used.iter().copied());
+
+        // The representative byte of each class (the unused sentinel aside)
+        // gives the live alphabet, already in ascending order.
+        let live_bytes: Vec<u8> = (1..classes.alphabet_len())
+            .map(|class| classes.byte(class as u8))
+            .collect();
+
         Ok(MiniSearch {
-            memory: Memory::open(filename, MemoryAccess::ReadOnly)?
+            memory,
+            live_bytes,
         })
     }
 
@@ -113,6 +325,7 @@ impl Search for MiniSearch {
         } else {
             let mini_search = MiniSearchIterator {
                 memory: &self.memory,
+                live_bytes: &self.live_bytes,
                 parents: vec![
                     MiniSearchIteratorIndex {
                         node_index: 0,
@@ -141,6 +354,8 @@ struct MiniSearchIteratorIndex {
 
 struct MiniSearchIterator<'a> {
     memory: &'a Memory<MiniNode>,
+    /// The live alphabet to iterate over instead of all 256 bytes.
+    live_bytes: &'a [u8],
     parents: Vec<MiniSearchIteratorIndex>,
     distance_calculator: &'a mut IncrementalDistance,
     max_distance: usize
@@ -151,10 +366,13 @@ impl <'a> Iterator for MiniSearchIterator<'a> {
 
     fn next(&mut self) -> Option<Self::Item> {
 
+        // How many live bytes there are to iterate over in each node.
+        let alphabet_len = self.live_bytes.len();
+
         while !self.parents.is_empty() {
 
             // Remove the impossible node
-            while self.parents.last()?.next_word_index == 256 {
+            while self.parents.last()?.next_word_index == alphabet_len {
                 self.parents.pop();
                 self.distance_calculator.pop();
             }
@@ -162,17 +380,19 @@ impl <'a> Iterator for MiniSearchIterator<'a> {
             // Read node
             let node = &self.memory[self.parents.last()?.node_index];
 
-            // Find the next used node
-            while self.parents.last()?.next_word_index < 256 && node.children[self.parents.last()?.next_word_index].is_none() {
+            // Find the next used node, walking only the live alphabet.
+            while self.parents.last()?.next_word_index < alphabet_len
+                && node.children[self.live_bytes[self.parents.last()?.next_word_index] as usize].is_none() {
                 self.parents.last_mut()?.next_word_index += 1;
             }
 
             // No node have been found in the current node, retrying.
-            if self.parents.last()?.next_word_index == 256 {
+            if self.parents.last()?.next_word_index == alphabet_len {
                 continue;
             }
 
-            let calculated_distance = self.distance_calculator.push(self.parents.last()?.next_word_index as u8);
+            let value = self.live_bytes[self.parents.last()?.next_word_index];
+            let calculated_distance = self.distance_calculator.push(value);
             self.parents.last_mut()?.next_word_index += 1;
 
 
@@ -183,7 +403,7 @@ impl <'a> Iterator for MiniSearchIterator<'a> {
             }
 
             // Go to the next node.
-            let children_node_index = node.children[self.parents.last()?.next_word_index - 1].unwrap().get();
+            let children_node_index = node.children[value as usize].unwrap().get();
             let children_node = &self.memory[children_node_index];
 
             self.parents.push(MiniSearchIteratorIndex {
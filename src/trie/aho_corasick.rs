@@ -0,0 +1,522 @@
+//! Multi-pattern substring scanning over the compiled reference trie.
+//!
+//! While `TrieSearch`/`MiniSearch` only answer "is this single word in the
+//! dictionary (within some edit distance)", this module answers the dual
+//! question: given an arbitrary input text, which dictionary words occur as
+//! a substring of it ?
+//!
+//! The compiled `Entry` array already gives the goto function of an
+//! Aho-Corasick automaton (a node is the index of its first entry, and the
+//! link entries are its outgoing edges). The only thing missing are the
+//! failure links and the output sets, both of which are built once at load
+//! time by a breadth first traversal over the existing node layout:
+//! - the root's direct children fail to the root,
+//! - for a node `u` reached from its parent `p` through byte `c`, `fail(u)`
+//!   is found by following `fail(p)` until a state has an outgoing edge on
+//!   `c` (or the root is reached),
+//! - `output(u)` is the union of the word ending in `u` (if any) and
+//!   `output(fail(u))`.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::io::{self, Read};
+
+use crate::memory::DiskMemory;
+use crate::WordFrequency;
+
+use super::{get, get_char, get_data, get_flag, Entry};
+
+/// The root node of the compiled trie.
+/// The first entry holds the word count and the second one the root node,
+/// exactly as `TrieSearch` skips them when it starts a search.
+const ROOT: usize = 2;
+
+/// Above this fraction of the text being trigger bytes, the prefilter is not
+/// worth it and the scan falls back to a plain linear pass.
+const MAX_TRIGGER_DENSITY: usize = 4; // i.e. more than a quarter of the bytes.
+
+/// A static, rough frequency rank of a byte in natural text: the higher the
+/// rank, the more common the byte, and thus the worse it is as a trigger.
+/// Rare bytes get the lowest ranks so that they are picked first.
+fn byte_rank(byte: u8) -> u32 {
+    match byte {
+        b' ' | b'\t' | b'\n' => 100,
+        b'e' | b'E' => 90,
+        b't' | b'T' => 88,
+        b'a' | b'A' => 86,
+        b'o' | b'O' => 84,
+        b'i' | b'I' => 82,
+        b'n' | b'N' => 80,
+        b's' | b'S' => 78,
+        b'h' | b'H' => 76,
+        b'r' | b'R' => 74,
+        b'd' | b'D' => 60,
+        b'l' | b'L' => 58,
+        b'c' | b'C' => 56,
+        b'u' | b'U' => 54,
+        b'm' | b'M' => 52,
+        _ if byte.is_ascii_alphabetic() => 30,
+        _ if byte.is_ascii_digit() => 10,
+        _ => 1, // Everything else makes an excellent trigger.
+    }
+}
+
+/// A dictionary word found inside the scanned text.
+pub struct Match {
+    /// The matched word, copied out of the input text.
+    pub word: Vec<u8>,
+    /// The frequency associated with the word in the dictionary.
+    pub frequency: WordFrequency,
+    /// The offset (in bytes) of the first character of the match in the text.
+    pub offset: usize,
+}
+
+/// Scan an arbitrary text and report every dictionary word occurring in it.
+/// The automaton is the compiled trie itself, augmented with the failure and
+/// output tables needed to turn it into an Aho-Corasick matcher.
+pub struct AhoCorasickSearch {
+    /// The disk memory that is been used to save all the nodes.
+    memory: DiskMemory,
+    /// The failure link of each reachable state (node first-entry index).
+    fail: HashMap<usize, usize>,
+    /// For each state, the words ending in it, already unioned with the
+    /// outputs reachable through the failure links. A word is stored as its
+    /// length (so that it can be sliced back out of the text) and frequency.
+    output: HashMap<usize, Vec<(usize, WordFrequency)>>,
+    /// The length of the longest dictionary word, i.e. how many recent bytes
+    /// the streaming scanner must keep around to rebuild a match.
+    max_len: usize,
+    /// When enabled, the set of "rarest trigger bytes": for each dictionary
+    /// word, the single byte with the lowest expected frequency. Every
+    /// occurrence of a word necessarily contains its trigger byte, so the
+    /// scan can jump directly from one trigger byte to the next and only run
+    /// the automaton around them.
+    triggers: Option<Box<[bool; 256]>>,
+}
+
+impl AhoCorasickSearch {
+    /// Load a compiled trie and build the failure and output tables on top of
+    /// its node layout so that it can be used as a multi-pattern matcher.
+    pub fn load(filename: &str) -> Result<Self, String> {
+        Self::load_filtered(filename, false)
+    }
+
+    /// Same as `load`, but with the rare-byte prefilter toggled on or off.
+    /// Enabling it speeds up scans of latency-sensitive callers without
+    /// changing which matches are returned.
+    pub fn load_filtered(filename: &str, prefilter: bool) -> Result<Self, String> {
+        let memory = DiskMemory::open(filename)?;
+
+        let mut search = AhoCorasickSearch {
+            memory,
+            fail: HashMap::new(),
+            output: HashMap::new(),
+            max_len: 0,
+            triggers: None,
+        };
+        search.build();
+
+        if prefilter {
+            search.triggers = Some(search.collect_triggers());
+        }
+
+        Ok(search)
+    }
+
+    /// Walk every dictionary word and record, for each, the single byte with
+    /// the lowest expected frequency as a trigger byte.
+    fn collect_triggers(&self) -> Box<[bool; 256]> {
+        let mut triggers = Box::new([false; 256]);
+        self.collect_triggers_rec(ROOT, &mut Vec::new(), &mut triggers);
+        triggers
+    }
+
+    fn collect_triggers_rec(&self, node: usize, word: &mut Vec<u8>, triggers: &mut [bool; 256]) {
+        if !self.terminal(node, word.len()).is_empty() {
+            if let Some(trigger) = word.iter().min_by_key(|byte| byte_rank(**byte)) {
+                triggers[*trigger as usize] = true;
+            }
+        }
+
+        for (value, child) in self.children(node) {
+            word.push(value);
+            self.collect_triggers_rec(child, word, triggers);
+            word.pop();
+        }
+    }
+
+    /// Scan the given text and return every dictionary word occurring in it,
+    /// in the order in which the matches end.
+    pub fn find(&self, text: &[u8]) -> Vec<Match> {
+        match &self.triggers {
+            // Only use the prefilter when the trigger bytes are actually rare
+            // in the text, otherwise a plain linear pass is cheaper.
+            Some(triggers)
+                if text.iter().filter(|byte| triggers[**byte as usize]).count() * MAX_TRIGGER_DENSITY
+                    <= text.len() =>
+            {
+                self.find_prefiltered(text, triggers)
+            }
+            _ => self.find_full(text),
+        }
+    }
+
+    /// Full linear scan of the text, running the automaton over every byte.
+    fn find_full(&self, text: &[u8]) -> Vec<Match> {
+        let mut matches = Vec::new();
+        let mut state = ROOT;
+
+        for (i, value) in text.iter().enumerate() {
+            // Follow the failure links until the current state has an edge on
+            // the read byte, or the root is reached.
+            loop {
+                if let Some(next) = self.goto(state, *value) {
+                    state = next;
+                    break;
+                }
+
+                if state == ROOT {
+                    break; // No edge from the root, stay on it.
+                }
+
+                state = self.fail[&state];
+            }
+
+            // Emit all the words ending in the current state.
+            if let Some(outputs) = self.output.get(&state) {
+                for (length, frequency) in outputs.iter() {
+                    let start = i + 1 - length;
+                    matches.push(Match {
+                        word: text[start..=i].to_vec(),
+                        frequency: *frequency,
+                        offset: start,
+                    });
+                }
+            }
+        }
+
+        matches
+    }
+
+    /// Scan the text using the rare-byte prefilter: jump from one trigger
+    /// byte to the next and only run the automaton over the small window
+    /// around each, where a match containing that trigger can live.
+    ///
+    /// Because every dictionary word contains its trigger byte, a match
+    /// spans at least one trigger; it is emitted exactly once (deduplicated
+    /// on its end position and length) when that trigger is processed.
+    fn find_prefiltered(&self, text: &[u8], triggers: &[bool; 256]) -> Vec<Match> {
+        let mut matches = Vec::new();
+        let mut emitted: HashSet<(usize, usize)> = HashSet::new();
+
+        let mut cursor = 0;
+        while cursor < text.len() {
+            // memchr-style jump to the next trigger byte.
+            let trigger = match text[cursor..]
+                .iter()
+                .position(|byte| triggers[*byte as usize])
+            {
+                Some(offset) => cursor + offset,
+                None => break,
+            };
+
+            // A match containing the trigger starts at most max_len - 1 bytes
+            // before it and ends at most max_len - 1 bytes after it.
+            let low = trigger.saturating_sub(self.max_len.saturating_sub(1));
+            let high = (trigger + self.max_len).min(text.len());
+
+            let mut state = ROOT;
+            for i in low..high {
+                let value = text[i];
+                loop {
+                    if let Some(next) = self.goto(state, value) {
+                        state = next;
+                        break;
+                    }
+
+                    if state == ROOT {
+                        break;
+                    }
+
+                    state = self.fail[&state];
+                }
+
+                if i < trigger {
+                    continue; // Still warming the automaton up to the trigger.
+                }
+
+                if let Some(outputs) = self.output.get(&state) {
+                    for (length, frequency) in outputs.iter() {
+                        let start = i + 1 - length;
+                        // Keep only the matches that actually span the trigger
+                        // so each one is reported once, when its trigger hits.
+                        if start <= trigger && emitted.insert((i, *length)) {
+                            matches.push(Match {
+                                word: text[start..=i].to_vec(),
+                                frequency: *frequency,
+                                offset: start,
+                            });
+                        }
+                    }
+                }
+            }
+
+            cursor = trigger + 1;
+        }
+
+        // Report in the same end-then-start order as the full scan.
+        matches.sort_by_key(|found| (found.offset + found.word.len(), found.offset));
+        matches
+    }
+
+    /// Scan a `Read` stream (a file, stdin, ...) without loading it all into
+    /// memory, reporting matches with absolute byte offsets.
+    ///
+    /// A fixed size buffer is refilled as needed while the automaton state is
+    /// carried across refills, so a word straddling a buffer boundary is
+    /// still found: it is the automaton state (not the raw bytes) that must
+    /// be preserved. A rolling window of the last `max_len` bytes is kept so
+    /// that such a match can be copied out even though its first bytes came
+    /// from an already discarded buffer.
+    pub fn stream_find(&self, mut reader: impl Read) -> io::Result<Vec<Match>> {
+        const BUFFER_SIZE: usize = 1 << 16;
+
+        let mut buffer = vec![0u8; BUFFER_SIZE];
+        let mut matches = Vec::new();
+        let mut state = ROOT;
+        // The absolute index of the next byte that will be consumed.
+        let mut absolute = 0;
+        // The most recently consumed bytes, at most `max_len` of them once
+        // trimmed, used to rebuild words crossing a buffer boundary.
+        let mut window: Vec<u8> = Vec::new();
+
+        loop {
+            let read = reader.read(&mut buffer)?;
+            if read == 0 {
+                break; // End of the stream.
+            }
+
+            for value in buffer[..read].iter() {
+                // Carry the automaton state across bytes (and thus refills).
+                loop {
+                    if let Some(next) = self.goto(state, *value) {
+                        state = next;
+                        break;
+                    }
+
+                    if state == ROOT {
+                        break;
+                    }
+
+                    state = self.fail[&state];
+                }
+
+                window.push(*value);
+                let position = absolute;
+                absolute += 1;
+
+                if let Some(outputs) = self.output.get(&state) {
+                    // The window currently covers the bytes in
+                    // [absolute - window.len(), absolute).
+                    let window_start = absolute - window.len();
+                    for (length, frequency) in outputs.iter() {
+                        let start = position + 1 - length;
+                        matches.push(Match {
+                            word: window[(start - window_start)..=(position - window_start)].to_vec(),
+                            frequency: *frequency,
+                            offset: start,
+                        });
+                    }
+                }
+
+                // Only the last `max_len` bytes can still be needed.
+                if window.len() > self.max_len {
+                    let excess = window.len() - self.max_len;
+                    window.drain(0..excess);
+                }
+            }
+        }
+
+        Ok(matches)
+    }
+
+    /// Build the failure and output tables with a breadth first traversal
+    /// over the compiled nodes.
+    fn build(&mut self) {
+        // The root and its direct children all fail to the root.
+        self.fail.insert(ROOT, ROOT);
+        self.output.insert(ROOT, self.terminal(ROOT, 0));
+
+        let mut depth: HashMap<usize, usize> = HashMap::new();
+        depth.insert(ROOT, 0);
+
+        let mut queue: VecDeque<(usize, u8, usize)> = VecDeque::new();
+        for (value, child) in self.children(ROOT) {
+            self.fail.insert(child, ROOT);
+            depth.insert(child, 1);
+            queue.push_back((ROOT, value, child));
+        }
+
+        while let Some((parent, value, node)) = queue.pop_front() {
+            // fail(node) is found by following fail(parent) until a state has
+            // an edge on `value`, or the root is reached.
+            let mut state = self.fail[&parent];
+            let fail = loop {
+                if let Some(next) = self.goto(state, value) {
+                    if next != node {
+                        break next;
+                    }
+                }
+
+                if state == ROOT {
+                    break ROOT;
+                }
+
+                state = self.fail[&state];
+            };
+            self.fail.insert(node, fail);
+
+            // output(node) = own word ∪ output(fail(node)). Because fail(node)
+            // is always shallower, its output is already computed.
+            let node_depth = depth[&node];
+            let mut outputs = self.terminal(node, node_depth);
+            if !outputs.is_empty() && node_depth > self.max_len {
+                self.max_len = node_depth;
+            }
+            if let Some(inherited) = self.output.get(&fail) {
+                outputs.extend_from_slice(inherited);
+            }
+            self.output.insert(node, outputs);
+
+            for (child_value, child) in self.children(node) {
+                depth.insert(child, node_depth + 1);
+                queue.push_back((node, child_value, child));
+            }
+        }
+    }
+
+    /// The word ending in the given node, if it holds a data entry.
+    /// A word is returned as its length (equal to the node depth) and
+    /// frequency so that it can later be sliced back out of the scanned text.
+    fn terminal(&self, node: usize, depth: usize) -> Vec<(usize, WordFrequency)> {
+        for offset in 0..256 {
+            let entry: Entry = *unsafe { get(&self.memory, node + offset) };
+
+            if get_char(entry) == 0 {
+                if let Some(frequency) = WordFrequency::new(get_data(entry)) {
+                    return vec![(depth, frequency)];
+                }
+            }
+
+            if get_flag(entry) {
+                break;
+            }
+        }
+
+        Vec::new()
+    }
+
+    /// Follow the goto edge of the node on the given byte, if it exists.
+    fn goto(&self, node: usize, value: u8) -> Option<usize> {
+        for offset in 0..256 {
+            let current_index = node + offset;
+            let entry: Entry = *unsafe { get(&self.memory, current_index) };
+
+            // char 0 marks the terminal entry (see `terminal` above), not a
+            // real edge, so a NUL byte in the scanned text must never match
+            // it here: dictionary words never contain 0, so the edge for an
+            // actual byte value of 0 simply doesn't exist.
+            if value != 0 && get_char(entry) == value {
+                return Some(current_index + get_data(entry) as usize);
+            }
+
+            if get_flag(entry) {
+                break;
+            }
+        }
+
+        None
+    }
+
+    /// All the outgoing edges of the node, as `(byte, child node)` pairs.
+    fn children(&self, node: usize) -> Vec<(u8, usize)> {
+        let mut children = Vec::new();
+
+        for offset in 0..256 {
+            let current_index = node + offset;
+            let entry: Entry = *unsafe { get(&self.memory, current_index) };
+
+            let value = get_char(entry);
+            if value != 0 {
+                children.push((value, current_index + get_data(entry) as usize));
+            }
+
+            if get_flag(entry) {
+                break;
+            }
+        }
+
+        children
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Encode a single-word trie as a linear chain of single-edge nodes, in
+    /// the on-disk `Entry` format this module reads: entry 0 is the word
+    /// count and entry 1 is reserved (see `ROOT`), then one edge entry per
+    /// byte of `word`, terminated by a char-0 data entry holding `frequency`.
+    /// Every node here has exactly one edge, so every entry is also its
+    /// node's last (the flag bit is always set).
+    fn build_single_word_trie(word: &[u8], frequency: u32) -> Vec<u8> {
+        let mut entries: Vec<Entry> = vec![1, 0];
+
+        for &byte in word {
+            let index = entries.len();
+            let child = index + 1;
+            entries.push(0x80_00_00_00 | ((byte as u32) << 24) | (child - index) as u32);
+        }
+        entries.push(0x80_00_00_00 | frequency);
+
+        entries.iter().flat_map(|entry| entry.to_ne_bytes()).collect()
+    }
+
+    /// A `Read` that only ever yields a handful of bytes per call, so a test
+    /// can exercise `stream_find`'s state-carrying across buffer refills
+    /// without needing a fixture large enough to fill the real 64K buffer.
+    struct ChunkedReader<'a> {
+        remaining: &'a [u8],
+        chunk: usize,
+    }
+
+    impl<'a> Read for ChunkedReader<'a> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let take = self.chunk.min(self.remaining.len()).min(buf.len());
+            buf[..take].copy_from_slice(&self.remaining[..take]);
+            self.remaining = &self.remaining[take..];
+            Ok(take)
+        }
+    }
+
+    #[test]
+    fn stream_find_across_buffer_refills() {
+        let bytes = build_single_word_trie(b"abcde", 7);
+        let path = format!("/tmp/aho_corasick_test_{}.trie", std::process::id());
+        std::fs::write(&path, &bytes).unwrap();
+
+        let search = AhoCorasickSearch::load(&path).unwrap();
+
+        let text = b"xxabcdexx";
+        let matches = search
+            .stream_find(ChunkedReader { remaining: text, chunk: 2 })
+            .unwrap();
+
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].word, b"abcde");
+        assert_eq!(matches[0].offset, 2);
+        assert_eq!(matches[0].frequency.get(), 7);
+    }
+}
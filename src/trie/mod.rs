@@ -16,8 +16,10 @@
 
 use crate::memory::DiskMemory;
 
+pub mod aho_corasick;
 pub mod searcher;
 
+pub use aho_corasick::AhoCorasickSearch;
 pub use searcher::TrieSearch;
 
 type Entry = u32;
@@ -1,10 +1,11 @@
-use crate::{Search, Information, WordData};
+use crate::{Search, Information, WordData, WordFrequency};
 use crate::memory::DiskMemory;
 use crate::distance::IncrementalDistance;
 
 use super::{Entry, get, get_flag, get_char, get_data};
 
 use core::num::NonZeroU32;
+use std::collections::BinaryHeap;
 
 pub struct TrieSearch {
     /// The disk memory that is been used to save all the nodes
@@ -36,6 +37,27 @@ impl Search for TrieSearch {
             Box::new(result.into_iter())
         }
     }
+
+    fn search_prefix(&self, prefix: &[u8], k: usize) -> Box<dyn Iterator<Item=WordData>> {
+        let mut found = Vec::new();
+
+        if let Some(index) = self.descend_prefix(2/* Skip length field and root node */, prefix) {
+            self.collect_subtree(index, prefix.into(), &mut found);
+        }
+
+        let mut result: Vec<WordData> = found
+            .into_iter()
+            .map(|(word, frequency)| WordData { word, frequency, distance: 0 })
+            .collect();
+
+        // Every candidate shares the same (zero) distance, so `WordData`'s
+        // order (distance asc, frequency desc, word asc) sorts by
+        // descending frequency exactly as wanted.
+        result.sort();
+        result.truncate(k);
+
+        Box::new(result.into_iter())
+    }
 }
 
 impl TrieSearch {
@@ -78,6 +100,113 @@ impl TrieSearch {
         None
     }
 
+    /// Search for the `k` closest words to the one held by `distance` instead
+    /// of every word under a fixed threshold.
+    ///
+    /// A max-heap keeps the current best `k` candidates keyed by their
+    /// `WordData` order (distance first). As soon as the heap is full, its
+    /// worst distance becomes the active `max_distance`, so the incremental
+    /// `can_continue` pruning tightens as better candidates are found. The
+    /// heap is finally drained into a distance-sorted vector.
+    pub fn search_top_k(&self, distance: &mut IncrementalDistance, k: usize) -> Vec<WordData> {
+        let mut heap = BinaryHeap::with_capacity(k);
+
+        if k != 0 {
+            self.top_k_search(2/* Skip length field and root node */, distance, k, &mut heap);
+        }
+
+        heap.into_sorted_vec()
+    }
+
+    fn top_k_search(&self, index: usize, distance: &mut IncrementalDistance,
+                    k: usize, heap: &mut BinaryHeap<WordData>) {
+        for offset in 0..256 {
+            let current_index = index + offset;
+            let entry: Entry = *unsafe { get(&self.memory, current_index) };
+
+            let current_c = get_char(entry);
+            if current_c == 0 {
+                // Data entry: keep it only if it beats the current worst.
+                let current_distance = distance.distance();
+                let candidate = WordData {
+                    word: distance.current().into(),
+                    frequency: NonZeroU32::new(get_data(entry)).unwrap(),
+                    distance: current_distance
+                };
+
+                if heap.len() < k {
+                    heap.push(candidate);
+                } else if heap.peek().map_or(false, |worst| candidate < *worst) {
+                    heap.pop();
+                    heap.push(candidate);
+                }
+            } else {
+                // Link entry: the threshold is the worst kept distance once the
+                // heap is full, and unbounded while it is still filling up.
+                distance.push(current_c);
+                let max_distance = if heap.len() >= k {
+                    heap.peek().map_or(usize::MAX, |worst| worst.distance)
+                } else {
+                    usize::MAX
+                };
+                if distance.can_continue(max_distance) {
+                    self.top_k_search(current_index + get_data(entry) as usize, distance, k, heap);
+                }
+                distance.pop();
+            }
+
+            if get_flag(entry) {
+                break;
+            }
+        }
+    }
+
+    /// Follow `prefix` one byte per level, returning the index of the node
+    /// reached once it is fully consumed, or `None` as soon as a byte has no
+    /// matching entry.
+    fn descend_prefix(&self, index: usize, prefix: &[u8]) -> Option<usize> {
+        if prefix.is_empty() {
+            return Some(index);
+        }
+
+        for offset in 0..256 {
+            let current_index = index + offset;
+            let entry: Entry = *unsafe { get(&self.memory, current_index) };
+
+            if get_char(entry) == prefix[0] {
+                return self.descend_prefix(current_index + get_data(entry) as usize, &prefix[1..]);
+            }
+
+            if get_flag(entry) {
+                break;
+            }
+        }
+
+        None
+    }
+
+    /// Walk a node's 256-entry run, collecting every word it contains (like
+    /// `words_rec`, but gathering the `(word, frequency)` pairs instead of
+    /// just counting them).
+    fn collect_subtree(&self, index: usize, word: Vec<u8>, result: &mut Vec<(Vec<u8>, WordFrequency)>) {
+        for offset in 0..256 {
+            let current_index = index + offset;
+            let entry: Entry = *unsafe { get(&self.memory, current_index) };
+
+            if get_char(entry) == 0 {
+                result.push((word.clone(), NonZeroU32::new(get_data(entry)).unwrap()));
+            } else {
+                let mut child_word = word.clone();
+                child_word.push(get_char(entry));
+                self.collect_subtree(current_index + get_data(entry) as usize, child_word, result);
+            }
+
+            if get_flag(entry) {
+                break;
+            }
+        }
+    }
+
     fn distance_search(&self, index: usize, distance: &mut IncrementalDistance,
                        max_distance: usize, result: &mut Vec<WordData>) {
         // Need to search for data
@@ -105,6 +234,142 @@ impl TrieSearch {
                 distance.pop();
             }
 
+            if get_flag(entry) {
+                break;
+            }
+        }
+    }
+}
+
+impl Information for TrieSearch {
+    fn words(&self) -> usize {
+        self.words_rec(2/* Skip length field and root node */)
+    }
+
+    fn nodes(&self) -> usize {
+        self.nodes_rec(2/* Skip length field and root node */)
+    }
+
+    fn height(&self) -> usize {
+        self.height_rec(2/* Skip length field and root node */)
+    }
+
+    fn max_lenght(&self) -> usize {
+        // Entries don't compress paths (one byte per link), so the longest
+        // word's length is the height of the trie.
+        self.height()
+    }
+
+    fn graph(&self) {
+        println!("digraph G {{");
+
+        self.graph_rec(2/* Skip length field and root node */);
+
+        println!("}}");
+    }
+}
+
+impl TrieSearch {
+    /// Walk a node's 256-entry run (the consecutive sibling list that
+    /// starts at `index`), counting one word per zero-character terminal
+    /// entry and recursing into every link entry's child run.
+    fn words_rec(&self, index: usize) -> usize {
+        let mut count = 0;
+
+        for offset in 0..256 {
+            let current_index = index + offset;
+            let entry: Entry = *unsafe { get(&self.memory, current_index) };
+
+            if get_char(entry) == 0 {
+                count += 1;
+            } else {
+                count += self.words_rec(current_index + get_data(entry) as usize);
+            }
+
+            if get_flag(entry) {
+                break;
+            }
+        }
+
+        count
+    }
+
+    fn nodes_rec(&self, index: usize) -> usize {
+        let mut count = 1;
+
+        for offset in 0..256 {
+            let current_index = index + offset;
+            let entry: Entry = *unsafe { get(&self.memory, current_index) };
+
+            if get_char(entry) != 0 {
+                count += self.nodes_rec(current_index + get_data(entry) as usize);
+            }
+
+            if get_flag(entry) {
+                break;
+            }
+        }
+
+        count
+    }
+
+    fn height_rec(&self, index: usize) -> usize {
+        let mut max_child_height = 0;
+
+        for offset in 0..256 {
+            let current_index = index + offset;
+            let entry: Entry = *unsafe { get(&self.memory, current_index) };
+
+            if get_char(entry) != 0 {
+                let child_height = self.height_rec(current_index + get_data(entry) as usize);
+                max_child_height = max_child_height.max(child_height);
+            }
+
+            if get_flag(entry) {
+                break;
+            }
+        }
+
+        1 + max_child_height
+    }
+
+    fn graph_rec(&self, index: usize) {
+        // The node's own frequency, if any of its entries is the
+        // zero-character terminal one.
+        let mut frequency = None;
+        for offset in 0..256 {
+            let entry: Entry = *unsafe { get(&self.memory, index + offset) };
+
+            if get_char(entry) == 0 {
+                frequency = Some(get_data(entry));
+            }
+
+            if get_flag(entry) {
+                break;
+            }
+        }
+
+        print!("{} [", index);
+
+        if let Some(frequency) = frequency {
+            print!("label=\"{}\", color=green, style=filled", frequency);
+        } else {
+            print!("label=\"\"");
+        }
+
+        println!("];");
+
+        for offset in 0..256 {
+            let current_index = index + offset;
+            let entry: Entry = *unsafe { get(&self.memory, current_index) };
+
+            let value = get_char(entry);
+            if value != 0 {
+                let child_index = current_index + get_data(entry) as usize;
+                println!("{} -> {} [label=\"{}\"];", index, child_index, value as char);
+                self.graph_rec(child_index);
+            }
+
             if get_flag(entry) {
                 break;
             }